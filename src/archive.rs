@@ -1,82 +1,207 @@
-use std;
+use std::collections::HashMap;
 use std::io;
-use std::fs;
-#[cfg(unix)]
-use std::os::unix::fs::PermissionsExt;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
 use zip;
+use zip::result::{ZipError, ZipResult};
+use zip::CompressionMethod;
+
+/// Location of a single file's data within a zip archive
+#[derive(Debug)]
+struct ZipEntry {
+    data_start: u64,
+    compressed_size: u64,
+    compression: CompressionMethod,
+    crc32: u32,
+}
 
-pub fn extract_zip<T: io::Read + io::Seek>(
-    archive: &mut zip::ZipArchive<T>,
-    output: &std::path::Path,
-) -> Result<(), zip::result::ZipError> {
-    for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let outpath = output.join(sanitize_filename(file.name()));
-
-        {
-            let comment = file.comment();
-            if comment.len() > 0 {
-                println!("  File comment: {}", comment);
+/// Index of the files in a zip archive that can open each one as a stream
+///
+/// The central directory is read once, afterwards every file is decompressed
+/// straight from the underlying reader so nothing is written to disk.
+#[derive(Debug)]
+pub struct ZipIndex<R> {
+    source: Arc<Mutex<R>>,
+    entries: HashMap<String, ZipEntry>,
+}
+
+impl<R: Read + Seek> ZipIndex<R> {
+    pub fn new(reader: R) -> ZipResult<ZipIndex<R>> {
+        let mut archive = zip::ZipArchive::new(reader)?;
+        let mut entries = HashMap::new();
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i)?;
+            if file.is_dir() {
+                continue;
             }
+            entries.insert(
+                file.name().to_string(),
+                ZipEntry {
+                    data_start: file.data_start(),
+                    compressed_size: file.compressed_size(),
+                    compression: file.compression(),
+                    crc32: file.crc32(),
+                },
+            );
         }
+        Ok(ZipIndex {
+            source: Arc::new(Mutex::new(archive.into_inner())),
+            entries,
+        })
+    }
 
-        let perms = convert_permissions(file.unix_mode());
-
-        // also suspicious but why not?
-        if (&*file.name()).ends_with("/") {
-            create_directory(&outpath, perms);
-        } else {
-            write_file(&mut file, &outpath, perms);
+    /// Opens the named file, returns `ZipError::FileNotFound` if it isn't in the archive
+    pub fn open(&self, name: &str) -> ZipResult<ZipEntryReader<R>> {
+        let entry = self.entries.get(name).ok_or(ZipError::FileNotFound)?;
+        let data = SharedReader {
+            source: Arc::clone(&self.source),
+            position: entry.data_start,
         }
+        .take(entry.compressed_size);
+        let contents = match entry.compression {
+            CompressionMethod::Stored => Contents::Stored(data),
+            CompressionMethod::Deflated => Contents::Deflated(DeflateDecoder::new(data)),
+            _ => {
+                return Err(ZipError::UnsupportedArchive(
+                    "Compression method not supported",
+                ))
+            }
+        };
+        Ok(ZipEntryReader {
+            contents,
+            crc: Crc::new(),
+            crc32: entry.crc32,
+        })
     }
-    Ok(())
 }
 
-#[cfg(unix)]
-fn convert_permissions(mode: Option<u32>) -> Option<fs::Permissions> {
-    match mode {
-        Some(mode) => Some(fs::Permissions::from_mode(mode)),
-        None => None,
+enum Contents<R> {
+    Stored(io::Take<SharedReader<R>>),
+    Deflated(DeflateDecoder<io::Take<SharedReader<R>>>),
+}
+
+/// Decompressed contents of a single file in a zip archive
+///
+/// The contents are checked against the CRC-32 of the archive as they are read,
+/// a mismatch is reported as `io::ErrorKind::InvalidData` once the file ends.
+pub struct ZipEntryReader<R> {
+    contents: Contents<R>,
+    crc: Crc,
+    crc32: u32,
+}
+
+impl<R: Read + Seek> Read for ZipEntryReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = match self.contents {
+            Contents::Stored(ref mut reader) => reader.read(buf)?,
+            Contents::Deflated(ref mut reader) => reader.read(buf)?,
+        };
+        self.crc.update(&buf[..read]);
+        if read == 0 && !buf.is_empty() && self.crc.sum() != self.crc32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Zip entry failed its CRC-32 check",
+            ));
+        }
+        Ok(read)
     }
 }
-#[cfg(not(unix))]
-fn convert_permissions(_mode: Option<u32>) -> Option<fs::Permissions> {
-    None
+
+/// Reader with its own position over a source shared with other readers
+///
+/// Each read seeks the source first, so several files from the same archive
+/// can be read at once.
+pub struct SharedReader<R> {
+    source: Arc<Mutex<R>>,
+    position: u64,
 }
 
-fn write_file(
-    file: &mut zip::read::ZipFile,
-    outpath: &std::path::Path,
-    perms: Option<fs::Permissions>,
-) {
-    let mut outfile = fs::File::create(&outpath).unwrap();
-    io::copy(file, &mut outfile).unwrap();
-    if let Some(perms) = perms {
-        fs::set_permissions(outpath, perms).unwrap();
+impl<R: Read + Seek> Read for SharedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut source = self
+            .source
+            .lock()
+            .map_err(|_| io::Error::other("zip archive lock poisoned"))?;
+        source.seek(SeekFrom::Start(self.position))?;
+        let read = source.read(buf)?;
+        self.position += read as u64;
+        Ok(read)
     }
 }
 
-fn create_directory(outpath: &std::path::Path, perms: Option<fs::Permissions>) {
-    fs::create_dir_all(&outpath).unwrap();
-    if let Some(perms) = perms {
-        fs::set_permissions(outpath, perms).unwrap();
+/// Formats zip errors with the detail the zip crate leaves out of its messages
+pub fn describe_zip_error(err: &ZipError) -> String {
+    match *err {
+        ZipError::InvalidArchive(detail) => format!("Invalid Zip archive: {}", detail),
+        ZipError::UnsupportedArchive(detail) => format!("Unsupported Zip archive: {}", detail),
+        ref e => format!("{}", e),
     }
 }
 
-fn sanitize_filename(filename: &str) -> std::path::PathBuf {
-    let no_null_filename = match filename.find('\0') {
-        Some(index) => &filename[0..index],
-        None => filename,
-    };
-
-    std::path::Path::new(no_null_filename)
-        .components()
-        .filter(|component| match *component {
-            std::path::Component::Normal(..) => true,
-            _ => false,
-        })
-        .fold(std::path::PathBuf::new(), |mut path, ref cur| {
-            path.push(cur.as_os_str());
-            path
-        })
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::FileOptions;
+
+    fn archive() -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("stored.txt", stored).unwrap();
+        zip.write_all(b"stored contents").unwrap();
+        zip.add_directory("nested/", FileOptions::default()).unwrap();
+        zip.start_file("nested/deflated.txt", FileOptions::default())
+            .unwrap();
+        zip.write_all(b"deflated contents").unwrap();
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn read_to_string<R: Read + Seek>(index: &ZipIndex<R>, name: &str) -> String {
+        let mut contents = String::new();
+        index
+            .open(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_open_entries() {
+        let index = ZipIndex::new(Cursor::new(archive())).unwrap();
+        assert_eq!("stored contents", read_to_string(&index, "stored.txt"));
+        assert_eq!(
+            "deflated contents",
+            read_to_string(&index, "nested/deflated.txt")
+        );
+        match index.open("nested/") {
+            Err(ZipError::FileNotFound) => (),
+            _ => panic!("directories should not be opened"),
+        }
+    }
+
+    #[test]
+    fn test_corrupted_entry() {
+        let mut bytes = archive();
+        let start = bytes
+            .windows(15)
+            .position(|window| window == b"stored contents")
+            .unwrap();
+        bytes[start] = b'S';
+        let index = ZipIndex::new(Cursor::new(bytes)).unwrap();
+        let mut contents = String::new();
+        let err = index
+            .open("stored.txt")
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert_eq!(
+            "deflated contents",
+            read_to_string(&index, "nested/deflated.txt")
+        );
+    }
 }
@@ -0,0 +1,244 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use feed::TransitFeed;
+use transit::{Calendar, CalendarDate, ExceptionType};
+
+/// Weekly pattern of a service from calendar.txt
+#[derive(Debug)]
+struct ServicePeriod {
+    days: [bool; 7],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+}
+
+impl ServicePeriod {
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        date >= self.start_date
+            && date <= self.end_date
+            && self.days[date.weekday().num_days_from_monday() as usize]
+    }
+}
+
+/// Index of `Calendar` and `CalendarDate` records answering which services run on a date
+#[derive(Debug, Default)]
+pub struct ServiceCalendar {
+    periods: HashMap<String, ServicePeriod>,
+    // true when service was added on the date, false when it was removed
+    exceptions: HashMap<String, BTreeMap<NaiveDate, bool>>,
+}
+
+impl ServiceCalendar {
+    pub fn new(feed: &TransitFeed) -> ServiceCalendar {
+        match feed.calendar_dates {
+            Some(ref calendar_dates) => {
+                ServiceCalendar::from_records(&feed.calendars, calendar_dates)
+            }
+            None => ServiceCalendar::from_records(&feed.calendars, &[]),
+        }
+    }
+
+    pub fn from_records(
+        calendars: &[Calendar],
+        calendar_dates: &[CalendarDate],
+    ) -> ServiceCalendar {
+        let mut service_calendar = ServiceCalendar::default();
+        for calendar in calendars {
+            service_calendar.periods.insert(
+                calendar.service_id.clone(),
+                ServicePeriod {
+                    days: [
+                        calendar.monday,
+                        calendar.tuesday,
+                        calendar.wednesday,
+                        calendar.thursday,
+                        calendar.friday,
+                        calendar.saturday,
+                        calendar.sunday,
+                    ],
+                    start_date: calendar.start_date,
+                    end_date: calendar.end_date,
+                },
+            );
+        }
+        for calendar_date in calendar_dates {
+            let added = match calendar_date.exception_type {
+                ExceptionType::ServiceAdded => true,
+                ExceptionType::ServiceRemoved => false,
+            };
+            service_calendar
+                .exceptions
+                .entry(calendar_date.service_id.clone())
+                .or_default()
+                .insert(calendar_date.date, added);
+        }
+        service_calendar
+    }
+
+    /// True if `service_id` runs on `date`, after applying calendar_dates.txt exceptions
+    pub fn is_active(&self, service_id: &str, date: NaiveDate) -> bool {
+        if let Some(added) = self
+            .exceptions
+            .get(service_id)
+            .and_then(|exceptions| exceptions.get(&date))
+        {
+            return *added;
+        }
+        self.periods
+            .get(service_id)
+            .is_some_and(|period| period.runs_on(date))
+    }
+
+    /// Every service_id that runs on `date`
+    pub fn active_services(&self, date: NaiveDate) -> HashSet<&str> {
+        self.service_ids()
+            .filter(|service_id| self.is_active(service_id, date))
+            .collect()
+    }
+
+    /// Every date `service_id` runs on, in order
+    pub fn service_dates(&self, service_id: &str) -> Vec<NaiveDate> {
+        let mut dates = vec![];
+        if let Some(period) = self.periods.get(service_id) {
+            let mut date = period.start_date;
+            while date <= period.end_date {
+                if self.is_active(service_id, date) {
+                    dates.push(date);
+                }
+                date += Duration::days(1);
+            }
+        }
+        if let Some(exceptions) = self.exceptions.get(service_id) {
+            for (date, added) in exceptions {
+                if *added && !dates.contains(date) {
+                    dates.push(*date);
+                }
+            }
+            dates.sort();
+        }
+        dates
+    }
+
+    /// First and last date that any service runs on
+    pub fn validity_range(&self) -> Option<(NaiveDate, NaiveDate)> {
+        self.service_ids()
+            .filter_map(|service_id| {
+                let dates = self.service_dates(service_id);
+                match (dates.first(), dates.last()) {
+                    (Some(first), Some(last)) => Some((*first, *last)),
+                    _ => None,
+                }
+            })
+            .fold(None, |range, (first, last)| match range {
+                Some((start, end)) => Some((first.min(start), last.max(end))),
+                None => Some((first, last)),
+            })
+    }
+
+    fn service_ids(&self) -> impl Iterator<Item = &str> {
+        let mut ids: HashSet<&str> = self.periods.keys().map(|id| id.as_str()).collect();
+        ids.extend(self.exceptions.keys().map(|id| id.as_str()));
+        ids.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn calendar(service_id: &str, weekdays: bool, weekends: bool) -> Calendar {
+        Calendar {
+            service_id: service_id.to_string(),
+            monday: weekdays,
+            tuesday: weekdays,
+            wednesday: weekdays,
+            thursday: weekdays,
+            friday: weekdays,
+            saturday: weekends,
+            sunday: weekends,
+            start_date: date(2026, 11, 1),
+            end_date: date(2026, 11, 30),
+        }
+    }
+
+    fn calendar_date(service_id: &str, date: NaiveDate, added: bool) -> CalendarDate {
+        CalendarDate {
+            service_id: service_id.to_string(),
+            date,
+            exception_type: if added {
+                ExceptionType::ServiceAdded
+            } else {
+                ExceptionType::ServiceRemoved
+            },
+        }
+    }
+
+    fn service_calendar() -> ServiceCalendar {
+        ServiceCalendar::from_records(
+            &[
+                calendar("WEEK", true, false),
+                calendar("WEEKEND", false, true),
+            ],
+            &[
+                // a holiday on Wednesday the 11th runs the weekend service
+                calendar_date("WEEK", date(2026, 11, 11), false),
+                calendar_date("WEEKEND", date(2026, 11, 11), true),
+                calendar_date("SPECIAL", date(2026, 12, 31), true),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_weekday_flags() {
+        let services = service_calendar();
+        assert!(services.is_active("WEEK", date(2026, 11, 3)));
+        assert!(!services.is_active("WEEKEND", date(2026, 11, 3)));
+        assert!(services.is_active("WEEKEND", date(2026, 11, 7)));
+        assert!(!services.is_active("WEEK", date(2026, 12, 1)));
+        assert!(!services.is_active("UNKNOWN", date(2026, 11, 3)));
+    }
+
+    #[test]
+    fn test_exceptions() {
+        let services = service_calendar();
+        let holiday = date(2026, 11, 11);
+        assert!(!services.is_active("WEEK", holiday));
+        assert!(services.is_active("WEEKEND", holiday));
+        assert_eq!(
+            vec!["WEEKEND"].into_iter().collect::<HashSet<_>>(),
+            services.active_services(holiday)
+        );
+        assert_eq!(
+            vec!["SPECIAL"].into_iter().collect::<HashSet<_>>(),
+            services.active_services(date(2026, 12, 31))
+        );
+    }
+
+    #[test]
+    fn test_service_dates() {
+        let services = service_calendar();
+        let weekend = services.service_dates("WEEKEND");
+        assert_eq!(10, weekend.len());
+        assert_eq!(date(2026, 11, 1), weekend[0]);
+        assert_eq!(date(2026, 11, 11), weekend[3]);
+        assert_eq!(20, services.service_dates("WEEK").len());
+        assert_eq!(vec![date(2026, 12, 31)], services.service_dates("SPECIAL"));
+    }
+
+    #[test]
+    fn test_validity_range() {
+        assert_eq!(
+            Some((date(2026, 11, 2), date(2026, 12, 31))),
+            ServiceCalendar::from_records(
+                &[calendar("WEEK", true, false)],
+                &[calendar_date("SPECIAL", date(2026, 12, 31), true)],
+            )
+            .validity_range()
+        );
+        assert_eq!(None, ServiceCalendar::default().validity_range());
+    }
+}
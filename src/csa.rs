@@ -0,0 +1,340 @@
+use chrono::{Duration, NaiveDate};
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+use calendar::ServiceCalendar;
+use feed::TransitFeed;
+use routing::{Footpaths, StopIndex};
+use run::RunIterator;
+use transit::{StopServiceType, TimeOffset};
+
+/// A vehicle travelling between two consecutive stops of a trip
+struct Connection {
+    trip: usize,
+    from: usize,
+    to: usize,
+    departure: u32,
+    arrival: u32,
+    /// Whether riders can board at `from`
+    pickup: bool,
+    /// Whether riders can get off at `to`
+    drop_off: bool,
+}
+
+/// An optimal departure of a profile, see `ConnectionScan::profile`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfileEntry {
+    pub departure: TimeOffset,
+    pub arrival: TimeOffset,
+}
+
+/// Earliest arrival and profile queries for a service day using the Connection Scan
+/// Algorithm
+///
+/// The stop times of every run on the day, including each departure of a
+/// frequency-based trip, are split into connections between consecutive stops and
+/// sorted by departure. Footpaths and the time needed to change trips come from
+/// transfers.txt as for `Router`.
+pub struct ConnectionScan<'a> {
+    feed: &'a TransitFeed,
+    stops: StopIndex<'a>,
+    connections: Vec<Connection>,
+    trip_count: usize,
+    footpaths: Footpaths,
+    /// Footpaths arriving at each stop and the seconds they take
+    incoming: Vec<Vec<(usize, u32)>>,
+}
+
+impl<'a> ConnectionScan<'a> {
+    pub fn new(feed: &'a TransitFeed, date: NaiveDate) -> ConnectionScan<'a> {
+        ConnectionScan::build(feed, date, None)
+    }
+
+    /// Like `new`, but riders may also walk between any stops within `radius` metres
+    ///
+    /// See `Footpaths::add_nearby`.
+    pub fn with_walking(feed: &'a TransitFeed, date: NaiveDate, radius: f64) -> ConnectionScan<'a> {
+        ConnectionScan::build(feed, date, Some(radius))
+    }
+
+    fn build(feed: &'a TransitFeed, date: NaiveDate, radius: Option<f64>) -> ConnectionScan<'a> {
+        let stops = StopIndex::new(feed);
+        let calendar = ServiceCalendar::new(feed);
+        let services = calendar.active_services(date);
+
+        let mut connections = vec![];
+        let mut trip_count = 0;
+        for run in RunIterator::new(feed.stoptimes.iter()) {
+            if !feed
+                .find_trip(&run.trip)
+                .is_some_and(|trip| services.contains(trip.service_id.as_str()))
+            {
+                continue;
+            }
+            let stop_times = match run.interpolate(feed) {
+                Some(stop_times) => stop_times,
+                None => continue,
+            };
+            let seconds = |time: Option<TimeOffset>| time.map_or(0, |time| time.total_seconds());
+            for (_, stop_times) in run.departures(feed, stop_times) {
+                for pair in stop_times.windows(2) {
+                    if let (Some(from), Some(to)) =
+                        (stops.get(&pair[0].stop_id), stops.get(&pair[1].stop_id))
+                    {
+                        connections.push(Connection {
+                            trip: trip_count,
+                            from,
+                            to,
+                            departure: seconds(pair[0].departure_time),
+                            arrival: seconds(pair[1].arrival_time),
+                            pickup: pair[0].pickup_type != StopServiceType::NoServiceAvailable,
+                            drop_off: pair[1].dropoff_type != StopServiceType::NoServiceAvailable,
+                        });
+                    }
+                }
+                trip_count += 1;
+            }
+        }
+        // stable so zero-length connections stay in the order of their trip
+        connections.sort_by_key(|connection| (connection.departure, connection.arrival));
+
+        let mut footpaths = Footpaths::new(feed, &stops);
+        if let Some(radius) = radius {
+            footpaths.add_nearby(feed, &stops, radius);
+        }
+        let mut incoming = vec![vec![]; stops.len()];
+        for from in 0..stops.len() {
+            for &(to, seconds) in footpaths.walks(from) {
+                incoming[to].push((from, seconds));
+            }
+        }
+        ConnectionScan {
+            feed,
+            stops,
+            connections,
+            trip_count,
+            footpaths,
+            incoming,
+        }
+    }
+
+    /// Earliest arrival at `to` leaving `from` at `departure`, `None` if it can't be reached
+    ///
+    /// A station can be given for either stop to use any of its platforms.
+    pub fn earliest_arrival(
+        &self,
+        from: &str,
+        to: &str,
+        departure: TimeOffset,
+    ) -> Option<TimeOffset> {
+        let targets = self.stops.group(self.feed, to);
+        let origins = self.stops.group(self.feed, from);
+        let arrival = self.scan(&origins, departure.total_seconds(), &targets, u32::MAX);
+        targets
+            .iter()
+            .map(|&target| arrival[target])
+            .min()
+            .filter(|&best| best != u32::MAX)
+            .map(TimeOffset::from_seconds)
+    }
+
+    /// Shortest travel time to every stop reachable from `from` within `limit`
+    ///
+    /// Riders may leave at any time between `earliest` and `latest`, so each stop gets
+    /// the best of those departures. Only stops with a travel time of at most `limit`
+    /// are included, `from` and its platforms among them with no travel time.
+    pub fn travel_times(
+        &self,
+        from: &str,
+        earliest: TimeOffset,
+        latest: TimeOffset,
+        limit: Duration,
+    ) -> HashMap<&'a str, Duration> {
+        let origins = self.stops.group(self.feed, from);
+        let (earliest, latest) = (earliest.total_seconds(), latest.total_seconds());
+        let limit = limit.num_seconds().max(0) as u32;
+
+        // the best journeys leave at the window's start or just in time for a departure
+        let mut walk = HashMap::new();
+        for &origin in &origins {
+            walk.insert(origin, 0);
+            for &(to, seconds) in self.footpaths.walks(origin) {
+                let best = walk.entry(to).or_insert(seconds);
+                *best = (*best).min(seconds);
+            }
+        }
+        let mut starts = vec![earliest];
+        for connection in &self.connections {
+            if let Some(&seconds) = walk.get(&connection.from) {
+                if connection.pickup && connection.departure >= earliest + seconds {
+                    let start = connection.departure - seconds;
+                    if start <= latest {
+                        starts.push(start);
+                    }
+                }
+            }
+        }
+        starts.sort_unstable();
+        starts.dedup();
+
+        let mut best = vec![u32::MAX; self.stops.len()];
+        for start in starts {
+            let arrival = self.scan(&origins, start, &[], start.saturating_add(limit));
+            for (best, &arrival) in best.iter_mut().zip(&arrival) {
+                if arrival != u32::MAX && arrival - start <= limit {
+                    *best = (*best).min(arrival - start);
+                }
+            }
+        }
+        best.iter()
+            .enumerate()
+            .filter(|&(_, &seconds)| seconds != u32::MAX)
+            .map(|(stop, &seconds)| (self.stops.ids[stop], Duration::seconds(i64::from(seconds))))
+            .collect()
+    }
+
+    /// `travel_times` for each of `origins`, computed in parallel
+    pub fn travel_time_matrix(
+        &self,
+        origins: &[&str],
+        earliest: TimeOffset,
+        latest: TimeOffset,
+        limit: Duration,
+    ) -> Vec<HashMap<&'a str, Duration>> {
+        origins
+            .par_iter()
+            .map(|from| self.travel_times(from, earliest, latest, limit))
+            .collect()
+    }
+
+    /// Earliest arrival at every stop leaving `origins` at `start`
+    ///
+    /// The scan stops once connections leave after `until` or after the earliest
+    /// arrival at any of `targets`.
+    fn scan(&self, origins: &[usize], start: u32, targets: &[usize], until: u32) -> Vec<u32> {
+        let mut arrival = vec![u32::MAX; self.stops.len()];
+        // earliest time a new trip can be boarded at each stop
+        let mut ready = vec![u32::MAX; self.stops.len()];
+        for &origin in origins {
+            arrival[origin] = start;
+            ready[origin] = start;
+            for &(to, seconds) in self.footpaths.walks(origin) {
+                arrival[to] = arrival[to].min(start + seconds);
+                ready[to] = ready[to].min(start + seconds);
+            }
+        }
+        let best = |arrival: &[u32]| targets.iter().map(|&target| arrival[target]).min();
+
+        let mut boarded = vec![false; self.trip_count];
+        let first = self
+            .connections
+            .partition_point(|connection| connection.departure < start);
+        for connection in &self.connections[first..] {
+            if connection.departure > until
+                || best(&arrival).is_some_and(|best| connection.departure >= best)
+            {
+                break;
+            }
+            let reached = boarded[connection.trip]
+                || (connection.pickup && ready[connection.from] <= connection.departure);
+            if !reached {
+                continue;
+            }
+            boarded[connection.trip] = true;
+            if connection.drop_off && connection.arrival < arrival[connection.to] {
+                arrival[connection.to] = connection.arrival;
+                if let Some(time) = self.footpaths.change(connection.to, connection.arrival) {
+                    ready[connection.to] = ready[connection.to].min(time);
+                }
+                for &(to, seconds) in self.footpaths.walks(connection.to) {
+                    let time = connection.arrival + seconds;
+                    arrival[to] = arrival[to].min(time);
+                    ready[to] = ready[to].min(time);
+                }
+            }
+        }
+        arrival
+    }
+
+    /// Every departure from `from` over the day that reaches `to` before any later one
+    ///
+    /// Entries are ordered by departure, which is the time of the first ride less any
+    /// walk to it. A station can be given for either stop to use any of its platforms.
+    pub fn profile(&self, from: &str, to: &str) -> Vec<ProfileEntry> {
+        let origins = self.stops.group(self.feed, from);
+        // seconds from each stop to the destination on foot
+        let mut final_walk = vec![u32::MAX; self.stops.len()];
+        for target in self.stops.group(self.feed, to) {
+            final_walk[target] = 0;
+            for &(from, seconds) in &self.incoming[target] {
+                final_walk[from] = final_walk[from].min(seconds);
+            }
+        }
+
+        let mut profiles: Vec<Vec<(u32, u32)>> = vec![vec![]; self.stops.len()];
+        // earliest arrival at the destination by staying on each trip
+        let mut trip_arrival = vec![u32::MAX; self.trip_count];
+        for connection in self.connections.iter().rev() {
+            let mut arrival = trip_arrival[connection.trip];
+            if connection.drop_off {
+                if final_walk[connection.to] != u32::MAX {
+                    arrival = arrival.min(connection.arrival + final_walk[connection.to]);
+                }
+                if let Some(ready) = self.footpaths.change(connection.to, connection.arrival) {
+                    arrival = arrival.min(earliest(&profiles[connection.to], ready));
+                }
+            }
+            if arrival == u32::MAX {
+                continue;
+            }
+            trip_arrival[connection.trip] = arrival;
+            if connection.pickup {
+                insert(
+                    &mut profiles[connection.from],
+                    (connection.departure, arrival),
+                );
+                for &(from, seconds) in &self.incoming[connection.from] {
+                    if connection.departure >= seconds {
+                        insert(
+                            &mut profiles[from],
+                            (connection.departure - seconds, arrival),
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut merged = vec![];
+        for origin in origins {
+            for &entry in &profiles[origin] {
+                insert(&mut merged, entry);
+            }
+        }
+        merged
+            .into_iter()
+            .map(|(departure, arrival)| ProfileEntry {
+                departure: TimeOffset::from_seconds(departure),
+                arrival: TimeOffset::from_seconds(arrival),
+            })
+            .collect()
+    }
+}
+
+/// Earliest arrival of a profile when leaving at `time` or later
+fn earliest(profile: &[(u32, u32)], time: u32) -> u32 {
+    let first = profile.partition_point(|&(departure, _)| departure < time);
+    profile.get(first).map_or(u32::MAX, |&(_, arrival)| arrival)
+}
+
+/// Adds a departure to a profile kept ordered by departure, with later departures
+/// arriving later, unless an entry leaving no earlier arrives no later
+fn insert(profile: &mut Vec<(u32, u32)>, (departure, arrival): (u32, u32)) {
+    if earliest(profile, departure) <= arrival {
+        return;
+    }
+    profile.retain(|&(other_departure, other_arrival)| {
+        other_departure > departure || other_arrival < arrival
+    });
+    let position = profile.partition_point(|&(other, _)| other < departure);
+    profile.insert(position, (departure, arrival));
+}
@@ -0,0 +1,514 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+
+use feed::TransitFeed;
+use transit::{FareAttribute, StopTime, TimeOffset, Transfers};
+
+/// A single ride of an itinerary to be priced
+#[derive(Debug, Clone, PartialEq)]
+pub struct FareLeg {
+    pub route_id: String,
+    /// Trip ridden, used to find the zones passed through between the origin and
+    /// destination. Without it only the zones of the origin and destination count.
+    pub trip_id: Option<String>,
+    /// stop_id the rider boards at
+    pub origin_id: String,
+    /// stop_id the rider gets off at
+    pub destination_id: String,
+    pub boarding_time: TimeOffset,
+}
+
+/// A fare paid for one or more consecutive legs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ticket {
+    pub fare_id: String,
+    pub price: f64,
+    pub currency_type: String,
+    /// Indices of the legs covered by this ticket
+    pub legs: Range<usize>,
+}
+
+/// Cheapest set of tickets in a single currency covering every leg of an itinerary
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fare {
+    pub tickets: Vec<Ticket>,
+    pub total: f64,
+    pub currency_type: String,
+}
+
+/// The conditions of one fare_rules.txt row for a fare
+///
+/// Rows that only differ by contains_id list the zones of a single condition.
+#[derive(Debug)]
+struct FareCondition<'a> {
+    fare: &'a FareAttribute,
+    route_id: Option<&'a str>,
+    origin_id: Option<&'a str>,
+    destination_id: Option<&'a str>,
+    contains: HashSet<&'a str>,
+}
+
+impl<'a> FareCondition<'a> {
+    fn matches(
+        &self,
+        routes: &HashSet<&str>,
+        origin: Option<&str>,
+        destination: Option<&str>,
+        zones: &HashSet<&str>,
+    ) -> bool {
+        self.route_id
+            .is_none_or(|route_id| routes.iter().all(|&route| route == route_id))
+            && self.origin_id.is_none_or(|zone| Some(zone) == origin)
+            && self
+                .destination_id
+                .is_none_or(|zone| Some(zone) == destination)
+            && (self.contains.is_empty() || *zones == self.contains)
+    }
+}
+
+/// Prices itineraries using fare_attributes.txt and fare_rules.txt
+///
+/// A fare can cover several consecutive legs if its `transfers` allows that many
+/// transfers, every leg boards within its `transfer_duration` and one of its rules
+/// matches the routes, origin and destination zones, and zones passed through by
+/// the legs. A fare without any rules applies to every leg.
+pub struct FareCalculator<'a> {
+    feed: &'a TransitFeed,
+    conditions: Vec<FareCondition<'a>>,
+    /// Stop times of each trip ordered by stop_sequence
+    trips: HashMap<&'a str, Vec<&'a StopTime>>,
+}
+
+impl<'a> FareCalculator<'a> {
+    pub fn new(feed: &'a TransitFeed) -> FareCalculator<'a> {
+        let fares: &[FareAttribute] = match feed.fare_attributes {
+            Some(ref fares) => fares,
+            None => &[],
+        };
+        let rules = match feed.fare_rules {
+            Some(ref rules) => &rules[..],
+            None => &[],
+        };
+        let mut conditions: Vec<FareCondition> = vec![];
+        for fare in fares {
+            let first = conditions.len();
+            for rule in rules.iter().filter(|rule| rule.fare_id == fare.fare_id) {
+                let (route_id, origin_id, destination_id) = (
+                    rule.route_id.as_deref(),
+                    rule.origin_id.as_deref(),
+                    rule.destination_id.as_deref(),
+                );
+                let existing = conditions[first..].iter().position(|condition| {
+                    (
+                        condition.route_id,
+                        condition.origin_id,
+                        condition.destination_id,
+                    ) == (route_id, origin_id, destination_id)
+                });
+                let index = match existing {
+                    Some(index) => first + index,
+                    None => {
+                        conditions.push(FareCondition {
+                            fare,
+                            route_id,
+                            origin_id,
+                            destination_id,
+                            contains: HashSet::new(),
+                        });
+                        conditions.len() - 1
+                    }
+                };
+                conditions[index]
+                    .contains
+                    .extend(rule.contains_id.as_deref());
+            }
+            if conditions.len() == first {
+                conditions.push(FareCondition {
+                    fare,
+                    route_id: None,
+                    origin_id: None,
+                    destination_id: None,
+                    contains: HashSet::new(),
+                });
+            }
+        }
+
+        let mut trips: HashMap<&str, Vec<&StopTime>> = HashMap::new();
+        for stop_time in &feed.stoptimes {
+            trips.entry(&stop_time.trip_id).or_default().push(stop_time);
+        }
+        for stop_times in trips.values_mut() {
+            stop_times.sort_by_key(|stop_time| stop_time.stop_sequence);
+        }
+        FareCalculator {
+            feed,
+            conditions,
+            trips,
+        }
+    }
+
+    /// Cheapest way to pay for `legs` in each currency with fares covering every leg
+    ///
+    /// Tickets in different currencies are never combined. Fares are ordered by
+    /// currency_type and the result is empty if some leg has no applicable fare.
+    pub fn cheapest_fares(&self, legs: &[FareLeg]) -> Vec<Fare> {
+        let currencies: BTreeSet<&str> = self
+            .conditions
+            .iter()
+            .map(|condition| condition.fare.currency_type.as_str())
+            .collect();
+        currencies
+            .into_iter()
+            .filter_map(|currency| self.cheapest_fare_in(legs, currency))
+            .collect()
+    }
+
+    fn cheapest_fare_in(&self, legs: &[FareLeg], currency: &str) -> Option<Fare> {
+        // best[i] is the cheapest set of tickets covering the first i legs
+        let mut best: Vec<Option<Fare>> = vec![None; legs.len() + 1];
+        best[0] = Some(Fare {
+            tickets: vec![],
+            total: 0.0,
+            currency_type: currency.to_string(),
+        });
+        for end in 1..legs.len() + 1 {
+            for start in 0..end {
+                let previous = match best[start] {
+                    Some(ref fare) => fare.clone(),
+                    None => continue,
+                };
+                let ticket = match self.cheapest_ticket(legs, start..end, currency) {
+                    Some(ticket) => ticket,
+                    None => continue,
+                };
+                let total = previous.total + ticket.price;
+                if best[end].as_ref().is_none_or(|fare| total < fare.total) {
+                    let mut tickets = previous.tickets;
+                    tickets.push(ticket);
+                    best[end] = Some(Fare {
+                        tickets,
+                        total,
+                        currency_type: previous.currency_type,
+                    });
+                }
+            }
+        }
+        best.pop().and_then(|fare| fare)
+    }
+
+    /// Cheapest single fare in `currency` that covers every leg in `range`
+    fn cheapest_ticket(
+        &self,
+        legs: &[FareLeg],
+        range: Range<usize>,
+        currency: &str,
+    ) -> Option<Ticket> {
+        let covered = &legs[range.clone()];
+        let transfers = covered.len() - 1;
+        let elapsed = covered[covered.len() - 1].boarding_time - covered[0].boarding_time;
+
+        let routes: HashSet<&str> = covered.iter().map(|leg| leg.route_id.as_str()).collect();
+        let origin = self.zone(&covered[0].origin_id);
+        let destination = self.zone(&covered[covered.len() - 1].destination_id);
+        let zones: HashSet<&str> = covered.iter().flat_map(|leg| self.zones(leg)).collect();
+
+        self.conditions
+            .iter()
+            .filter(|condition| {
+                let fare = condition.fare;
+                fare.currency_type == currency
+                    && allows_transfers(&fare.transfers, transfers)
+                    && fare
+                        .transfer_duration
+                        .is_none_or(|duration| elapsed <= duration)
+                    && condition.matches(&routes, origin, destination, &zones)
+            })
+            .min_by(|a, b| a.fare.price.total_cmp(&b.fare.price))
+            .map(|condition| Ticket {
+                fare_id: condition.fare.fare_id.clone(),
+                price: condition.fare.price,
+                currency_type: condition.fare.currency_type.clone(),
+                legs: range,
+            })
+    }
+
+    /// Zones of the stops a leg visits, including those it passes through
+    fn zones(&self, leg: &FareLeg) -> Vec<&'a str> {
+        let stop_times = leg
+            .trip_id
+            .as_ref()
+            .and_then(|trip_id| self.trips.get(trip_id.as_str()));
+        let stop_ids: Vec<&str> = match stop_times.and_then(|stop_times| {
+            let start = stop_times
+                .iter()
+                .position(|stop_time| stop_time.stop_id == leg.origin_id)?;
+            let end = start
+                + stop_times[start..]
+                    .iter()
+                    .position(|stop_time| stop_time.stop_id == leg.destination_id)?;
+            Some(&stop_times[start..end + 1])
+        }) {
+            Some(ridden) => ridden
+                .iter()
+                .map(|stop_time| stop_time.stop_id.as_str())
+                .collect(),
+            None => vec![&leg.origin_id, &leg.destination_id],
+        };
+        stop_ids
+            .into_iter()
+            .filter_map(|stop_id| self.zone(stop_id))
+            .collect()
+    }
+
+    fn zone(&self, stop_id: &str) -> Option<&'a str> {
+        self.feed
+            .find_stop(stop_id)
+            .and_then(|stop| stop.zone_id.as_deref())
+    }
+}
+
+fn allows_transfers(allowed: &Transfers, transfers: usize) -> bool {
+    match *allowed {
+        Transfers::None => transfers == 0,
+        Transfers::TransferOnce => transfers <= 1,
+        Transfers::TransferTwice => transfers <= 2,
+        Transfers::Unlimited => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    const FARE_ATTRIBUTES: &str =
+        "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+         LOCAL_FARE,2.00,USD,0,1,3600\n\
+         EXPRESS_FARE,5.00,USD,1,0,\n\
+         ZONE_FARE,3.00,USD,1,,\n";
+
+    const FARE_RULES: &str = "fare_id,route_id,origin_id,destination_id,contains_id\n\
+                              LOCAL_FARE,LOCAL,,,\n\
+                              EXPRESS_FARE,EXPRESS,,,\n\
+                              ZONE_FARE,,Z1,Z2,\n";
+
+    fn fare_feed(fare_attributes: &'static str, fare_rules: &'static str) -> TransitFeed {
+        let provider: MemoryFeedProvider = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,zone_id\n\
+                 A,Alpha,1.0,1.0,Z1\n\
+                 B,Beta,1.0,1.1,Z1\n\
+                 C,Gamma,1.0,1.2,Z2\n\
+                 D,Delta,1.0,1.3,Z2\n\
+                 E,Epsilon,1.1,1.05,Z3\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\n\
+                 LOCAL,1,Local,3\n\
+                 EXPRESS,2,Express,3\n\
+                 NIGHT,3,Night,3\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nLOCAL,S,T\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,A,1\n\
+                 T,08:05:00,08:05:00,E,2\n\
+                 T,08:10:00,08:10:00,B,3\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+            ("fare_attributes.txt", fare_attributes),
+            ("fare_rules.txt", fare_rules),
+        ]
+        .into_iter()
+        .collect();
+        TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap()
+    }
+
+    fn leg(
+        route_id: &str,
+        origin_id: &str,
+        destination_id: &str,
+        hour: u32,
+        minute: u32,
+    ) -> FareLeg {
+        FareLeg {
+            route_id: route_id.to_string(),
+            trip_id: None,
+            origin_id: origin_id.to_string(),
+            destination_id: destination_id.to_string(),
+            boarding_time: TimeOffset::from_hms(hour, minute, 0),
+        }
+    }
+
+    fn tickets(fare: &Fare) -> Vec<(&str, Range<usize>)> {
+        fare.tickets
+            .iter()
+            .map(|ticket| (ticket.fare_id.as_str(), ticket.legs.clone()))
+            .collect()
+    }
+
+    fn cheapest(calculator: &FareCalculator, legs: &[FareLeg]) -> Option<Fare> {
+        let mut fares = calculator.cheapest_fares(legs);
+        assert!(fares.len() <= 1, "{:?}", fares);
+        fares.pop()
+    }
+
+    #[test]
+    fn test_single_leg() {
+        let feed = fare_feed(FARE_ATTRIBUTES, FARE_RULES);
+        let fare = cheapest(&FareCalculator::new(&feed), &[leg("LOCAL", "A", "B", 8, 0)]).unwrap();
+        assert_eq!(2.0, fare.total);
+        assert_eq!("USD", fare.currency_type);
+        assert_eq!(vec![("LOCAL_FARE", 0..1)], tickets(&fare));
+        assert_eq!("USD", fare.tickets[0].currency_type);
+    }
+
+    #[test]
+    fn test_transfer_within_duration() {
+        let feed = fare_feed(FARE_ATTRIBUTES, FARE_RULES);
+        let calculator = FareCalculator::new(&feed);
+        let fare = cheapest(
+            &calculator,
+            &[leg("LOCAL", "A", "B", 8, 0), leg("LOCAL", "B", "A", 8, 30)],
+        )
+        .unwrap();
+        assert_eq!(vec![("LOCAL_FARE", 0..2)], tickets(&fare));
+
+        // the transfer has expired by 09:30
+        let fare = cheapest(
+            &calculator,
+            &[leg("LOCAL", "A", "B", 8, 0), leg("LOCAL", "B", "A", 9, 30)],
+        )
+        .unwrap();
+        assert_eq!(4.0, fare.total);
+        assert_eq!(
+            vec![("LOCAL_FARE", 0..1), ("LOCAL_FARE", 1..2)],
+            tickets(&fare)
+        );
+
+        // only one transfer is allowed
+        let fare = cheapest(
+            &calculator,
+            &[
+                leg("LOCAL", "A", "B", 8, 0),
+                leg("LOCAL", "B", "A", 8, 10),
+                leg("LOCAL", "A", "B", 8, 20),
+            ],
+        )
+        .unwrap();
+        assert_eq!(4.0, fare.total);
+    }
+
+    #[test]
+    fn test_zone_fare_is_cheaper_than_route_fares() {
+        let feed = fare_feed(FARE_ATTRIBUTES, FARE_RULES);
+        let fare = cheapest(
+            &FareCalculator::new(&feed),
+            &[
+                leg("LOCAL", "A", "B", 8, 0),
+                leg("EXPRESS", "B", "C", 8, 15),
+            ],
+        )
+        .unwrap();
+        assert_eq!(3.0, fare.total);
+        assert_eq!(vec![("ZONE_FARE", 0..2)], tickets(&fare));
+    }
+
+    #[test]
+    fn test_no_applicable_fare() {
+        let feed = fare_feed(FARE_ATTRIBUTES, FARE_RULES);
+        let calculator = FareCalculator::new(&feed);
+        assert!(calculator
+            .cheapest_fares(&[leg("NIGHT", "C", "D", 23, 0)])
+            .is_empty());
+        // the first leg has a fare but the second doesn't
+        assert!(calculator
+            .cheapest_fares(&[leg("LOCAL", "C", "D", 8, 0), leg("NIGHT", "C", "D", 23, 0)])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rules_match_on_their_own() {
+        // no single rule allows LOCAL from Z1 to Z2
+        let feed = fare_feed(
+            "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+             ZONE_FARE,3.00,USD,1,,\n",
+            "fare_id,route_id,origin_id,destination_id,contains_id\n\
+             ZONE_FARE,LOCAL,Z9,Z9,\n\
+             ZONE_FARE,EXPRESS,Z1,Z2,\n",
+        );
+        let calculator = FareCalculator::new(&feed);
+        assert!(calculator
+            .cheapest_fares(&[leg("LOCAL", "A", "C", 8, 0)])
+            .is_empty());
+        let fare = cheapest(&calculator, &[leg("EXPRESS", "A", "C", 8, 0)]).unwrap();
+        assert_eq!(vec![("ZONE_FARE", 0..1)], tickets(&fare));
+    }
+
+    #[test]
+    fn test_contains_zones_passed_through() {
+        let feed = fare_feed(
+            "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+             INNER_FARE,1.00,USD,0,,\n\
+             OUTER_FARE,1.50,USD,0,,\n",
+            "fare_id,route_id,origin_id,destination_id,contains_id\n\
+             INNER_FARE,,,,Z1\n\
+             OUTER_FARE,,,,Z1\n\
+             OUTER_FARE,,,,Z3\n",
+        );
+        let calculator = FareCalculator::new(&feed);
+        let fare = cheapest(&calculator, &[leg("LOCAL", "A", "B", 8, 0)]).unwrap();
+        assert_eq!(vec![("INNER_FARE", 0..1)], tickets(&fare));
+
+        // trip T passes through E in zone Z3 between A and B
+        let mut through = leg("LOCAL", "A", "B", 8, 0);
+        through.trip_id = Some("T".to_string());
+        let fare = cheapest(&calculator, &[through]).unwrap();
+        assert_eq!(vec![("OUTER_FARE", 0..1)], tickets(&fare));
+    }
+
+    #[test]
+    fn test_currencies_are_not_mixed() {
+        let feed = fare_feed(
+            "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+             LOCAL_FARE,2.00,USD,0,0,\n\
+             LOCAL_CAD,1.50,CAD,0,0,\n\
+             EXPRESS_FARE,5.00,USD,1,0,\n",
+            "fare_id,route_id,origin_id,destination_id,contains_id\n\
+             LOCAL_FARE,LOCAL,,,\n\
+             LOCAL_CAD,LOCAL,,,\n\
+             EXPRESS_FARE,EXPRESS,,,\n",
+        );
+        let calculator = FareCalculator::new(&feed);
+        let fares = calculator.cheapest_fares(&[leg("LOCAL", "A", "B", 8, 0)]);
+        let totals: Vec<(&str, f64)> = fares
+            .iter()
+            .map(|fare| (fare.currency_type.as_str(), fare.total))
+            .collect();
+        assert_eq!(vec![("CAD", 1.5), ("USD", 2.0)], totals);
+
+        // the express leg can only be paid in USD so the local leg is too
+        let fares = calculator.cheapest_fares(&[
+            leg("LOCAL", "A", "B", 8, 0),
+            leg("EXPRESS", "B", "C", 8, 15),
+        ]);
+        assert_eq!(1, fares.len());
+        assert_eq!(7.0, fares[0].total);
+        assert!(fares[0]
+            .tickets
+            .iter()
+            .all(|ticket| ticket.currency_type == "USD"));
+    }
+}
@@ -1,20 +1,24 @@
-extern crate tempfile;
-
-use self::tempfile::{Builder, TempDir};
 use csv;
 use serde;
 use std;
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::io;
+use std::io::{Read, Seek, Write};
 use std::path::Path;
 use zip;
 
-use archive::extract_zip;
+use archive::{describe_zip_error, ZipEntryReader, ZipIndex};
 use gtfs::Error;
 use gtfs::GTFSIterator;
+use locations::{read_locations, write_locations, Location};
 use transit::{
-    Agency, Calendar, CalendarDate, FareAttribute, FareRule, FeedInfo, Frequency, Route,
-    ShapePoint, Stop, StopTime, Transfer, Trip,
+    Agency, Area, Attribution, BookingRule, Calendar, CalendarDate, FareAttribute, FareLegRule,
+    FareMedia, FareProduct, FareRule, FareTransferRule, FeedInfo, Frequency, Level, LocationGroup,
+    LocationGroupStop, LocationType, Network, Pathway, Route, RouteNetwork, ShapePoint, Stop,
+    StopArea, StopTime, Timeframe, Transfer, Translation, Trip,
 };
 
 pub use csv::{Terminator, Trim};
@@ -28,8 +32,17 @@ where
     builder: csv::ReaderBuilder,
 }
 
+/// Source of the files that make up a feed
 pub trait FeedProvider {
-    fn path(&self) -> &str;
+    type Reader: Read;
+
+    /// Opens the named file within the feed
+    fn open(&self, filename: &str) -> Result<Self::Reader, Error>;
+
+    /// Describes where the named file comes from for error messages
+    fn source(&self, filename: &str) -> String {
+        filename.to_string()
+    }
 }
 
 pub struct LocalFeedProvider {
@@ -45,33 +58,113 @@ impl LocalFeedProvider {
 }
 
 impl FeedProvider for LocalFeedProvider {
-    fn path(&self) -> &str {
-        return &self.path;
+    type Reader = File;
+
+    fn open(&self, filename: &str) -> Result<File, Error> {
+        File::open(Path::new(&self.path).join(filename))
+            .map_err(|e| Error::Csv(self.source(filename), e.into()))
+    }
+
+    fn source(&self, filename: &str) -> String {
+        Path::new(&self.path)
+            .join(filename)
+            .to_string_lossy()
+            .into_owned()
     }
 }
 
+/// Streams each file straight out of a zip archive
 #[derive(Debug)]
-pub struct ZipFeedProvider {
-    dir: TempDir,
+pub struct ZipFeedProvider<R = File> {
+    name: Option<String>,
+    index: ZipIndex<R>,
 }
 
-impl ZipFeedProvider {
-    fn new(zipfile: &str) -> Result<ZipFeedProvider, Error> {
-        let dir = Builder::new()
-            .prefix("transitfeed")
-            .tempdir()
-            .map_err(|e| Error::Feed(format!("{}", e)))?;
-        let mut zip =
-            zip::ZipArchive::new(File::open(zipfile).map_err(|e| Error::Feed(format!("{}", e)))?)
-                .map_err(|e| Error::Feed(format!("{}", e)))?;
-        extract_zip(&mut zip, dir.path()).map_err(|e| Error::Feed(format!("{}", e)))?;
-        Ok(ZipFeedProvider { dir: dir })
+impl ZipFeedProvider<File> {
+    fn new(zipfile: &str) -> Result<ZipFeedProvider<File>, Error> {
+        let file = File::open(zipfile).map_err(|e| Error::Feed(format!("{}", e)))?;
+        let mut provider = ZipFeedProvider::from_reader(file)?;
+        provider.name = Some(zipfile.to_string());
+        Ok(provider)
     }
 }
 
-impl FeedProvider for ZipFeedProvider {
-    fn path(&self) -> &str {
-        self.dir.path().to_str().unwrap()
+impl<R: Read + Seek> ZipFeedProvider<R> {
+    fn from_reader(reader: R) -> Result<ZipFeedProvider<R>, Error> {
+        let index = ZipIndex::new(reader).map_err(|e| Error::Feed(describe_zip_error(&e)))?;
+        Ok(ZipFeedProvider { name: None, index })
+    }
+}
+
+impl<R: Read + Seek> FeedProvider for ZipFeedProvider<R> {
+    type Reader = ZipEntryReader<R>;
+
+    fn open(&self, filename: &str) -> Result<ZipEntryReader<R>, Error> {
+        self.index.open(filename).map_err(|e| match e {
+            zip::result::ZipError::FileNotFound => Error::Csv(
+                self.source(filename),
+                io::Error::new(io::ErrorKind::NotFound, "file not found in archive").into(),
+            ),
+            e => Error::Feed(describe_zip_error(&e)),
+        })
+    }
+
+    fn source(&self, filename: &str) -> String {
+        match self.name {
+            Some(ref name) => format!("{}/{}", name, filename),
+            None => filename.to_string(),
+        }
+    }
+}
+
+/// Serves files from memory, mostly useful for tests
+///
+/// ```
+/// use transitfeed::feed::MemoryFeedProvider;
+/// use transitfeed::FeedReader;
+///
+/// let provider: MemoryFeedProvider = vec![(
+///     "agency.txt",
+///     "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+/// )]
+/// .into_iter()
+/// .collect();
+/// let feed = FeedReader::from_provider(provider);
+/// assert_eq!(1, feed.agencies().unwrap().count());
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryFeedProvider {
+    files: HashMap<String, String>,
+}
+
+impl MemoryFeedProvider {
+    pub fn new(files: HashMap<String, String>) -> MemoryFeedProvider {
+        MemoryFeedProvider { files }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for MemoryFeedProvider {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(files: I) -> Self {
+        MemoryFeedProvider::new(
+            files
+                .into_iter()
+                .map(|(name, contents)| (name.into(), contents.into()))
+                .collect(),
+        )
+    }
+}
+
+impl FeedProvider for MemoryFeedProvider {
+    type Reader = io::Cursor<Vec<u8>>;
+
+    fn open(&self, filename: &str) -> Result<io::Cursor<Vec<u8>>, Error> {
+        match self.files.get(filename) {
+            Some(contents) => Ok(io::Cursor::new(contents.clone().into_bytes())),
+            None => Err(Error::Csv(
+                filename.to_string(),
+                io::Error::new(io::ErrorKind::NotFound, "file not found in memory").into(),
+            )),
+        }
     }
 }
 
@@ -87,6 +180,22 @@ impl FeedReader<ZipFeedProvider> {
     }
 }
 
+impl<R: Read + Seek> FeedReader<ZipFeedProvider<R>> {
+    /// Reads a zip archive from any seekable source, such as an open file or network buffer
+    pub fn from_zip_reader(reader: R) -> Result<Self, Error> {
+        Ok(FeedReader::from_provider(ZipFeedProvider::from_reader(
+            reader,
+        )?))
+    }
+}
+
+impl<B: AsRef<[u8]>> FeedReader<ZipFeedProvider<io::Cursor<B>>> {
+    /// Reads a zip archive held in memory, either owned (`Vec<u8>`) or borrowed (`&[u8]`)
+    pub fn from_zip_bytes(bytes: B) -> Result<Self, Error> {
+        FeedReader::from_zip_reader(io::Cursor::new(bytes))
+    }
+}
+
 impl<P: FeedProvider> FeedReader<P> {
     pub fn from_provider(provider: P) -> Self {
         FeedReader {
@@ -99,77 +208,717 @@ impl<P: FeedProvider> FeedReader<P> {
         &mut self.builder
     }
 
-    pub fn agencies(&self) -> Result<GTFSIterator<File, Agency>, Error> {
+    pub fn agencies(&self) -> Result<GTFSIterator<P::Reader, Agency>, Error> {
         self.make_iterator("agency.txt")
     }
 
-    pub fn stops(&self) -> Result<GTFSIterator<File, Stop>, Error> {
+    pub fn stops(&self) -> Result<GTFSIterator<P::Reader, Stop>, Error> {
         self.make_iterator("stops.txt")
     }
 
-    pub fn routes(&self) -> Result<GTFSIterator<File, Route>, Error> {
+    pub fn routes(&self) -> Result<GTFSIterator<P::Reader, Route>, Error> {
         self.make_iterator("routes.txt")
     }
 
-    pub fn trips(&self) -> Result<GTFSIterator<File, Trip>, Error> {
+    pub fn trips(&self) -> Result<GTFSIterator<P::Reader, Trip>, Error> {
         self.make_iterator("trips.txt")
     }
 
-    pub fn stop_times(&self) -> Result<GTFSIterator<File, StopTime>, Error> {
+    pub fn stop_times(&self) -> Result<GTFSIterator<P::Reader, StopTime>, Error> {
         self.make_iterator("stop_times.txt")
     }
 
-    pub fn calendars(&self) -> Result<GTFSIterator<File, Calendar>, Error> {
+    pub fn calendars(&self) -> Result<GTFSIterator<P::Reader, Calendar>, Error> {
         self.make_iterator("calendar.txt")
     }
 
-    pub fn calendar_dates(&self) -> Result<GTFSIterator<File, CalendarDate>, Error> {
+    pub fn calendar_dates(&self) -> Result<GTFSIterator<P::Reader, CalendarDate>, Error> {
         self.make_iterator("calendar_dates.txt")
     }
 
-    pub fn fare_attributes(&self) -> Result<GTFSIterator<File, FareAttribute>, Error> {
+    pub fn fare_attributes(&self) -> Result<GTFSIterator<P::Reader, FareAttribute>, Error> {
         self.make_iterator("fare_attributes.txt")
     }
 
-    pub fn fare_rules(&self) -> Result<GTFSIterator<File, FareRule>, Error> {
+    pub fn fare_rules(&self) -> Result<GTFSIterator<P::Reader, FareRule>, Error> {
         self.make_iterator("fare_rules.txt")
     }
 
-    pub fn shapes(&self) -> Result<GTFSIterator<File, ShapePoint>, Error> {
+    pub fn fare_media(&self) -> Result<GTFSIterator<P::Reader, FareMedia>, Error> {
+        self.make_iterator("fare_media.txt")
+    }
+
+    pub fn fare_products(&self) -> Result<GTFSIterator<P::Reader, FareProduct>, Error> {
+        self.make_iterator("fare_products.txt")
+    }
+
+    pub fn fare_leg_rules(&self) -> Result<GTFSIterator<P::Reader, FareLegRule>, Error> {
+        self.make_iterator("fare_leg_rules.txt")
+    }
+
+    pub fn fare_transfer_rules(&self) -> Result<GTFSIterator<P::Reader, FareTransferRule>, Error> {
+        self.make_iterator("fare_transfer_rules.txt")
+    }
+
+    pub fn areas(&self) -> Result<GTFSIterator<P::Reader, Area>, Error> {
+        self.make_iterator("areas.txt")
+    }
+
+    pub fn stop_areas(&self) -> Result<GTFSIterator<P::Reader, StopArea>, Error> {
+        self.make_iterator("stop_areas.txt")
+    }
+
+    pub fn networks(&self) -> Result<GTFSIterator<P::Reader, Network>, Error> {
+        self.make_iterator("networks.txt")
+    }
+
+    pub fn route_networks(&self) -> Result<GTFSIterator<P::Reader, RouteNetwork>, Error> {
+        self.make_iterator("route_networks.txt")
+    }
+
+    pub fn timeframes(&self) -> Result<GTFSIterator<P::Reader, Timeframe>, Error> {
+        self.make_iterator("timeframes.txt")
+    }
+
+    pub fn shapes(&self) -> Result<GTFSIterator<P::Reader, ShapePoint>, Error> {
         self.make_iterator("shapes.txt")
     }
 
-    pub fn frequencies(&self) -> Result<GTFSIterator<File, Frequency>, Error> {
+    pub fn frequencies(&self) -> Result<GTFSIterator<P::Reader, Frequency>, Error> {
         self.make_iterator("frequencies.txt")
     }
 
-    pub fn transfers(&self) -> Result<GTFSIterator<File, Transfer>, Error> {
+    pub fn transfers(&self) -> Result<GTFSIterator<P::Reader, Transfer>, Error> {
         self.make_iterator("transfers.txt")
     }
 
-    pub fn feed_info(&self) -> Result<GTFSIterator<File, FeedInfo>, Error> {
+    pub fn pathways(&self) -> Result<GTFSIterator<P::Reader, Pathway>, Error> {
+        self.make_iterator("pathways.txt")
+    }
+
+    pub fn levels(&self) -> Result<GTFSIterator<P::Reader, Level>, Error> {
+        self.make_iterator("levels.txt")
+    }
+
+    pub fn feed_info(&self) -> Result<GTFSIterator<P::Reader, FeedInfo>, Error> {
         self.make_iterator("feed_info.txt")
     }
 
-    fn make_iterator<T>(&self, filename: &str) -> Result<GTFSIterator<File, T>, Error>
+    pub fn translations(&self) -> Result<GTFSIterator<P::Reader, Translation>, Error> {
+        self.make_iterator("translations.txt")
+    }
+
+    pub fn attributions(&self) -> Result<GTFSIterator<P::Reader, Attribution>, Error> {
+        self.make_iterator("attributions.txt")
+    }
+
+    pub fn booking_rules(&self) -> Result<GTFSIterator<P::Reader, BookingRule>, Error> {
+        self.make_iterator("booking_rules.txt")
+    }
+
+    pub fn location_groups(&self) -> Result<GTFSIterator<P::Reader, LocationGroup>, Error> {
+        self.make_iterator("location_groups.txt")
+    }
+
+    pub fn location_group_stops(
+        &self,
+    ) -> Result<GTFSIterator<P::Reader, LocationGroupStop>, Error> {
+        self.make_iterator("location_group_stops.txt")
+    }
+
+    /// Reads the GTFS-Flex zones in locations.geojson
+    pub fn locations(&self) -> Result<Vec<Location>, Error> {
+        let reader = self.provider.open("locations.geojson")?;
+        read_locations(reader, &self.provider.source("locations.geojson"))
+    }
+
+    fn make_iterator<T>(&self, filename: &str) -> Result<GTFSIterator<P::Reader, T>, Error>
     where
         T: serde::de::DeserializeOwned,
     {
-        let path = match Path::new(&self.provider.path()).join(filename).to_str() {
-            Some(path_str) => path_str.to_string(),
-            None => {
-                return Err(Error::Feed(format!(
-                    "failed to construct path from {} and {}",
-                    self.provider.path(),
-                    filename
-                )))
+        let reader = self.provider.open(filename)?;
+        GTFSIterator::new(self.builder.from_reader(reader), &self.provider.source(filename))
+    }
+}
+
+/// Destination for the files written by a `FeedWriter`
+pub trait FeedSink {
+    /// Returns a writer for the named file within the feed
+    fn writer(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error>;
+
+    /// Completes the feed once every file has been written
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+pub struct LocalFeedSink {
+    path: String,
+}
+
+impl LocalFeedSink {
+    fn new(path: &str) -> LocalFeedSink {
+        LocalFeedSink {
+            path: path.to_string(),
+        }
+    }
+}
+
+impl FeedSink for LocalFeedSink {
+    fn writer(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error> {
+        fs::create_dir_all(&self.path).map_err(|e| Error::Feed(format!("{}", e)))?;
+        let path = Path::new(&self.path).join(filename);
+        let file = File::create(&path).map_err(|e| Error::Feed(format!("{}", e)))?;
+        Ok(Box::new(file))
+    }
+}
+
+pub struct ZipFeedSink<W: Write + Seek> {
+    zip: zip::ZipWriter<W>,
+}
+
+impl<W: Write + Seek> ZipFeedSink<W> {
+    pub fn new(writer: W) -> ZipFeedSink<W> {
+        ZipFeedSink {
+            zip: zip::ZipWriter::new(writer),
+        }
+    }
+}
+
+impl<W: Write + Seek> FeedSink for ZipFeedSink<W> {
+    fn writer(&mut self, filename: &str) -> Result<Box<dyn Write + '_>, Error> {
+        self.zip
+            .start_file(filename, zip::write::FileOptions::default())
+            .map_err(|e| Error::Feed(format!("{}", e)))?;
+        Ok(Box::new(&mut self.zip))
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.zip
+            .finish()
+            .map(|_| ())
+            .map_err(|e| Error::Feed(format!("{}", e)))
+    }
+}
+
+/// Writes transit records as GTFS files
+pub struct FeedWriter<S>
+where
+    S: FeedSink,
+{
+    sink: S,
+    builder: csv::WriterBuilder,
+}
+
+impl FeedWriter<LocalFeedSink> {
+    pub fn new(path: &str) -> Self {
+        FeedWriter::from_sink(LocalFeedSink::new(path))
+    }
+}
+
+impl FeedWriter<ZipFeedSink<File>> {
+    pub fn to_zip(zipfile: &str) -> Result<Self, Error> {
+        let file = File::create(zipfile).map_err(|e| Error::Feed(format!("{}", e)))?;
+        Ok(FeedWriter::from_sink(ZipFeedSink::new(file)))
+    }
+}
+
+impl<S: FeedSink> FeedWriter<S> {
+    pub fn from_sink(sink: S) -> Self {
+        FeedWriter {
+            sink,
+            builder: csv::WriterBuilder::new(),
+        }
+    }
+
+    pub fn builder(&mut self) -> &mut csv::WriterBuilder {
+        &mut self.builder
+    }
+
+    pub fn write_agencies<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Agency>,
+    {
+        self.write_records("agency.txt", records)
+    }
+
+    pub fn write_stops<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Stop>,
+    {
+        self.write_records("stops.txt", records)
+    }
+
+    pub fn write_routes<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Route>,
+    {
+        self.write_records("routes.txt", records)
+    }
+
+    pub fn write_trips<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Trip>,
+    {
+        self.write_records("trips.txt", records)
+    }
+
+    pub fn write_stop_times<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a StopTime>,
+    {
+        self.write_records("stop_times.txt", records)
+    }
+
+    pub fn write_calendars<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Calendar>,
+    {
+        self.write_records("calendar.txt", records)
+    }
+
+    pub fn write_calendar_dates<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a CalendarDate>,
+    {
+        self.write_records("calendar_dates.txt", records)
+    }
+
+    pub fn write_fare_attributes<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareAttribute>,
+    {
+        self.write_records("fare_attributes.txt", records)
+    }
+
+    pub fn write_fare_rules<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareRule>,
+    {
+        self.write_records("fare_rules.txt", records)
+    }
+
+    pub fn write_fare_media<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareMedia>,
+    {
+        self.write_records("fare_media.txt", records)
+    }
+
+    pub fn write_fare_products<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareProduct>,
+    {
+        self.write_records("fare_products.txt", records)
+    }
+
+    pub fn write_fare_leg_rules<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareLegRule>,
+    {
+        self.write_records("fare_leg_rules.txt", records)
+    }
+
+    pub fn write_fare_transfer_rules<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a FareTransferRule>,
+    {
+        self.write_records("fare_transfer_rules.txt", records)
+    }
+
+    pub fn write_areas<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Area>,
+    {
+        self.write_records("areas.txt", records)
+    }
+
+    pub fn write_stop_areas<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a StopArea>,
+    {
+        self.write_records("stop_areas.txt", records)
+    }
+
+    pub fn write_networks<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Network>,
+    {
+        self.write_records("networks.txt", records)
+    }
+
+    pub fn write_route_networks<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a RouteNetwork>,
+    {
+        self.write_records("route_networks.txt", records)
+    }
+
+    pub fn write_timeframes<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Timeframe>,
+    {
+        self.write_records("timeframes.txt", records)
+    }
+
+    pub fn write_shapes<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a ShapePoint>,
+    {
+        self.write_records("shapes.txt", records)
+    }
+
+    pub fn write_frequencies<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Frequency>,
+    {
+        self.write_records("frequencies.txt", records)
+    }
+
+    pub fn write_transfers<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Transfer>,
+    {
+        self.write_records("transfers.txt", records)
+    }
+
+    pub fn write_pathways<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Pathway>,
+    {
+        self.write_records("pathways.txt", records)
+    }
+
+    pub fn write_levels<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Level>,
+    {
+        self.write_records("levels.txt", records)
+    }
+
+    pub fn write_translations<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Translation>,
+    {
+        self.write_records("translations.txt", records)
+    }
+
+    pub fn write_attributions<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Attribution>,
+    {
+        self.write_records("attributions.txt", records)
+    }
+
+    pub fn write_booking_rules<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a BookingRule>,
+    {
+        self.write_records("booking_rules.txt", records)
+    }
+
+    pub fn write_location_groups<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a LocationGroup>,
+    {
+        self.write_records("location_groups.txt", records)
+    }
+
+    pub fn write_location_group_stops<'a, I>(&mut self, records: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a LocationGroupStop>,
+    {
+        self.write_records("location_group_stops.txt", records)
+    }
+
+    pub fn write_locations<'a, I>(&mut self, locations: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = &'a Location>,
+    {
+        write_locations(self.sink.writer("locations.geojson")?, locations)
+    }
+
+    pub fn write_feed_info(&mut self, record: &FeedInfo) -> Result<(), Error> {
+        self.write_records("feed_info.txt", Some(record))
+    }
+
+    /// Writes every file present in `feed`, skipping optional files it doesn't have
+    pub fn write_feed(&mut self, feed: &TransitFeed) -> Result<(), Error> {
+        self.write_agencies(&feed.agencies)?;
+        self.write_stops(&feed.stops)?;
+        self.write_routes(&feed.routes)?;
+        self.write_trips(&feed.trips)?;
+        self.write_stop_times(&feed.stoptimes)?;
+        self.write_calendars(&feed.calendars)?;
+        if let Some(ref records) = feed.calendar_dates {
+            self.write_calendar_dates(records)?;
+        }
+        if let Some(ref records) = feed.fare_attributes {
+            self.write_fare_attributes(records)?;
+        }
+        if let Some(ref records) = feed.fare_rules {
+            self.write_fare_rules(records)?;
+        }
+        if let Some(ref records) = feed.fare_media {
+            self.write_fare_media(records)?;
+        }
+        if let Some(ref records) = feed.fare_products {
+            self.write_fare_products(records)?;
+        }
+        if let Some(ref records) = feed.fare_leg_rules {
+            self.write_fare_leg_rules(records)?;
+        }
+        if let Some(ref records) = feed.fare_transfer_rules {
+            self.write_fare_transfer_rules(records)?;
+        }
+        if let Some(ref records) = feed.areas {
+            self.write_areas(records)?;
+        }
+        if let Some(ref records) = feed.stop_areas {
+            self.write_stop_areas(records)?;
+        }
+        if let Some(ref records) = feed.networks {
+            self.write_networks(records)?;
+        }
+        if let Some(ref records) = feed.route_networks {
+            self.write_route_networks(records)?;
+        }
+        if let Some(ref records) = feed.timeframes {
+            self.write_timeframes(records)?;
+        }
+        if let Some(ref records) = feed.shapes {
+            self.write_shapes(records)?;
+        }
+        if let Some(ref records) = feed.frequencies {
+            self.write_frequencies(records)?;
+        }
+        if let Some(ref records) = feed.transfers {
+            self.write_transfers(records)?;
+        }
+        if let Some(ref records) = feed.pathways {
+            self.write_pathways(records)?;
+        }
+        if let Some(ref records) = feed.levels {
+            self.write_levels(records)?;
+        }
+        if let Some(ref records) = feed.translations {
+            self.write_translations(records)?;
+        }
+        if let Some(ref records) = feed.attributions {
+            self.write_attributions(records)?;
+        }
+        if let Some(ref records) = feed.booking_rules {
+            self.write_booking_rules(records)?;
+        }
+        if let Some(ref records) = feed.location_groups {
+            self.write_location_groups(records)?;
+        }
+        if let Some(ref records) = feed.location_group_stops {
+            self.write_location_group_stops(records)?;
+        }
+        if let Some(ref locations) = feed.locations {
+            self.write_locations(locations)?;
+        }
+        if let Some(ref record) = feed.feedinfo {
+            self.write_feed_info(record)?;
+        }
+        Ok(())
+    }
+
+    /// Completes the feed, this must be called for zip archives to be readable
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.sink.finish()
+    }
+
+    fn write_records<'a, T, I>(&mut self, filename: &str, records: I) -> Result<(), Error>
+    where
+        T: serde::Serialize + 'a,
+        I: IntoIterator<Item = &'a T>,
+    {
+        let mut writer = self.builder.from_writer(self.sink.writer(filename)?);
+        for record in records {
+            writer
+                .serialize(record)
+                .map_err(|e| Error::Csv(filename.to_string(), e))?;
+        }
+        writer
+            .flush()
+            .map_err(|e| Error::Csv(filename.to_string(), e.into()))
+    }
+}
+
+/// How `TransitFeed` handles records and files that fail to load
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadPolicy {
+    /// Fail on the first error
+    Strict,
+    /// Skip bad records, collecting their errors in the `LoadReport`
+    Lenient,
+}
+
+/// Everything that went wrong, or was missing, while loading a `TransitFeed`
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Errors for records that could not be parsed and were skipped
+    pub skipped: Vec<Error>,
+    /// Optional files that were not in the feed
+    pub missing_files: Vec<String>,
+    /// Number of records loaded from each file
+    pub counts: HashMap<String, usize>,
+    /// Problems with the feed that don't belong to a single record
+    pub warnings: Vec<String>,
+}
+
+impl LoadReport {
+    /// True if no records were skipped and there were no warnings
+    pub fn is_clean(&self) -> bool {
+        self.skipped.is_empty() && self.warnings.is_empty()
+    }
+}
+
+struct FeedLoader {
+    policy: LoadPolicy,
+    report: LoadReport,
+    lines: HashMap<String, Vec<u64>>,
+}
+
+impl FeedLoader {
+    fn load<R, T>(&mut self, filename: &str, mut iter: GTFSIterator<R, T>) -> Result<Vec<T>, Error>
+    where
+        R: std::io::Read,
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        let mut records = vec![];
+        let mut lines = vec![];
+        while let Some(result) = iter.next() {
+            match result {
+                Ok(record) => {
+                    records.push(record);
+                    lines.push(iter.line().unwrap_or(0));
+                }
+                Err(e) => self.skip(e)?,
             }
-        };
-        let reader = match self.builder.from_path(&path) {
-            Ok(reader) => reader,
-            Err(e) => return Err(Error::Csv(path, e)),
-        };
-        Ok(GTFSIterator::new(reader, &path)?)
+        }
+        self.report
+            .counts
+            .insert(filename.to_string(), records.len());
+        self.lines.insert(filename.to_string(), lines);
+        Ok(records)
+    }
+
+    fn load_optional<R, T>(
+        &mut self,
+        filename: &str,
+        result: Result<GTFSIterator<R, T>, Error>,
+    ) -> Result<Option<Vec<T>>, Error>
+    where
+        R: std::io::Read,
+        for<'de> T: serde::Deserialize<'de>,
+    {
+        match result {
+            Ok(iter) => self.load(filename, iter).map(Some),
+            Err(ref e) if is_missing_file(e) => {
+                self.report.missing_files.push(filename.to_string());
+                Ok(None)
+            }
+            Err(e) => self.skip(e).map(|_| None),
+        }
+    }
+
+    fn load_locations(
+        &mut self,
+        result: Result<Vec<Location>, Error>,
+    ) -> Result<Option<Vec<Location>>, Error> {
+        let filename = "locations.geojson";
+        match result {
+            Ok(locations) => {
+                self.report
+                    .counts
+                    .insert(filename.to_string(), locations.len());
+                Ok(Some(locations))
+            }
+            Err(ref e) if is_missing_file(e) => {
+                self.report.missing_files.push(filename.to_string());
+                Ok(None)
+            }
+            Err(e) => self.skip(e).map(|_| None),
+        }
+    }
+
+    fn skip(&mut self, err: Error) -> Result<(), Error> {
+        match self.policy {
+            LoadPolicy::Strict => Err(err),
+            LoadPolicy::Lenient => {
+                self.report.skipped.push(err);
+                Ok(())
+            }
+        }
+    }
+
+    fn warn(&mut self, message: String) -> Result<(), Error> {
+        match self.policy {
+            LoadPolicy::Strict => Err(Error::Feed(message)),
+            LoadPolicy::Lenient => {
+                self.report.warnings.push(message);
+                Ok(())
+            }
+        }
+    }
+}
+
+fn is_missing_file(err: &Error) -> bool {
+    match *err {
+        Error::Csv(_, ref e) => match *e.kind() {
+            csv::ErrorKind::Io(ref e) => e.kind() == std::io::ErrorKind::NotFound,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// What a row of translations.txt applies to
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct TranslationKey {
+    table_name: String,
+    field_name: String,
+    language: String,
+    record_id: Option<String>,
+    record_sub_id: Option<String>,
+    field_value: Option<String>,
+}
+
+impl TranslationKey {
+    fn from_record(translation: &Translation) -> TranslationKey {
+        TranslationKey {
+            table_name: translation.table_name.clone(),
+            field_name: translation.field_name.clone(),
+            language: translation.language.clone(),
+            record_id: translation.record_id.clone(),
+            record_sub_id: translation.record_sub_id.clone(),
+            field_value: translation.field_value.clone(),
+        }
+    }
+
+    fn record(table: &str, field: &str, language: &str, id: &str, sub_id: Option<String>) -> Self {
+        TranslationKey {
+            table_name: table.to_string(),
+            field_name: field.to_string(),
+            language: language.to_string(),
+            record_id: Some(id.to_string()),
+            record_sub_id: sub_id,
+            field_value: None,
+        }
+    }
+
+    fn value(table: &str, field: &str, language: &str, value: &str) -> Self {
+        TranslationKey {
+            table_name: table.to_string(),
+            field_name: field.to_string(),
+            language: language.to_string(),
+            record_id: None,
+            record_sub_id: None,
+            field_value: Some(value.to_string()),
+        }
     }
 }
 
@@ -184,61 +933,228 @@ pub struct TransitFeed {
     pub calendar_dates: Option<Vec<CalendarDate>>,
     pub fare_attributes: Option<Vec<FareAttribute>>,
     pub fare_rules: Option<Vec<FareRule>>,
+    pub fare_media: Option<Vec<FareMedia>>,
+    pub fare_products: Option<Vec<FareProduct>>,
+    pub fare_leg_rules: Option<Vec<FareLegRule>>,
+    pub fare_transfer_rules: Option<Vec<FareTransferRule>>,
+    pub areas: Option<Vec<Area>>,
+    pub stop_areas: Option<Vec<StopArea>>,
+    pub networks: Option<Vec<Network>>,
+    pub route_networks: Option<Vec<RouteNetwork>>,
+    pub timeframes: Option<Vec<Timeframe>>,
     pub shapes: Option<Vec<ShapePoint>>,
     pub frequencies: Option<Vec<Frequency>>,
     pub transfers: Option<Vec<Transfer>>,
+    pub pathways: Option<Vec<Pathway>>,
+    pub levels: Option<Vec<Level>>,
+    pub translations: Option<Vec<Translation>>,
+    pub attributions: Option<Vec<Attribution>>,
+    pub booking_rules: Option<Vec<BookingRule>>,
+    pub location_groups: Option<Vec<LocationGroup>>,
+    pub location_group_stops: Option<Vec<LocationGroupStop>>,
+    pub locations: Option<Vec<Location>>,
     pub feedinfo: Option<FeedInfo>,
 
     stop_map: HashMap<String, usize>,
+    children_map: HashMap<String, Vec<usize>>,
     route_map: HashMap<String, usize>,
     trip_map: HashMap<String, usize>,
+    frequency_map: HashMap<String, Vec<usize>>,
+    translation_map: HashMap<TranslationKey, usize>,
+
+    report: LoadReport,
+    lines: HashMap<String, Vec<u64>>,
 }
 
 impl TransitFeed {
+    /// Loads every file in the feed, skipping records that fail to parse
     pub fn from_reader<P: FeedProvider>(reader: &FeedReader<P>) -> Result<TransitFeed, Error> {
-        let agencies = load_feed_file(try!(reader.agencies()));
-        let stops = load_feed_file(try!(reader.stops()));
-        let routes = load_feed_file(try!(reader.routes()));
-        let trips = load_feed_file(try!(reader.trips()));
-        let stoptimes = load_feed_file(try!(reader.stop_times()));
-        let calendars = load_feed_file(try!(reader.calendars()));
+        TransitFeed::from_reader_with_policy(reader, LoadPolicy::Lenient)
+    }
+
+    pub fn from_reader_with_policy<P: FeedProvider>(
+        reader: &FeedReader<P>,
+        policy: LoadPolicy,
+    ) -> Result<TransitFeed, Error> {
+        let mut loader = FeedLoader {
+            policy,
+            report: LoadReport::default(),
+            lines: HashMap::new(),
+        };
+        let agencies = loader.load("agency.txt", reader.agencies()?)?;
+        let stops = loader.load("stops.txt", reader.stops()?)?;
+        let routes = loader.load("routes.txt", reader.routes()?)?;
+        let trips = loader.load("trips.txt", reader.trips()?)?;
+        let stoptimes = loader.load("stop_times.txt", reader.stop_times()?)?;
+        let calendars = loader.load("calendar.txt", reader.calendars()?)?;
+        let calendar_dates = loader.load_optional("calendar_dates.txt", reader.calendar_dates())?;
+        let fare_attributes =
+            loader.load_optional("fare_attributes.txt", reader.fare_attributes())?;
+        let fare_rules = loader.load_optional("fare_rules.txt", reader.fare_rules())?;
+        let fare_media = loader.load_optional("fare_media.txt", reader.fare_media())?;
+        let fare_products = loader.load_optional("fare_products.txt", reader.fare_products())?;
+        let fare_leg_rules = loader.load_optional("fare_leg_rules.txt", reader.fare_leg_rules())?;
+        let fare_transfer_rules =
+            loader.load_optional("fare_transfer_rules.txt", reader.fare_transfer_rules())?;
+        let areas = loader.load_optional("areas.txt", reader.areas())?;
+        let stop_areas = loader.load_optional("stop_areas.txt", reader.stop_areas())?;
+        let networks = loader.load_optional("networks.txt", reader.networks())?;
+        let route_networks = loader.load_optional("route_networks.txt", reader.route_networks())?;
+        let timeframes = loader.load_optional("timeframes.txt", reader.timeframes())?;
+        let shapes = loader.load_optional("shapes.txt", reader.shapes())?;
+        let frequencies = loader.load_optional("frequencies.txt", reader.frequencies())?;
+        let transfers = loader.load_optional("transfers.txt", reader.transfers())?;
+        let pathways = loader.load_optional("pathways.txt", reader.pathways())?;
+        let levels = loader.load_optional("levels.txt", reader.levels())?;
+        let translations = loader.load_optional("translations.txt", reader.translations())?;
+        let attributions = loader.load_optional("attributions.txt", reader.attributions())?;
+        let booking_rules = loader.load_optional("booking_rules.txt", reader.booking_rules())?;
+        let location_groups =
+            loader.load_optional("location_groups.txt", reader.location_groups())?;
+        let location_group_stops =
+            loader.load_optional("location_group_stops.txt", reader.location_group_stops())?;
+        let locations = loader.load_locations(reader.locations())?;
+        let feedinfo = match loader.load_optional("feed_info.txt", reader.feed_info())? {
+            Some(mut records) => {
+                if records.len() != 1 {
+                    loader.warn(format!(
+                        "expected 1 entry in feed_info.txt but found {}",
+                        records.len()
+                    ))?;
+                }
+                records.pop()
+            }
+            None => None,
+        };
 
         let stop_map = make_map(&stops, |stop: &Stop| stop.stop_id.clone());
+        let mut children_map: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, stop) in stops.iter().enumerate() {
+            if let Some(ref parent_id) = stop.parent_station {
+                children_map
+                    .entry(parent_id.clone())
+                    .or_default()
+                    .push(index);
+            }
+        }
         let route_map = make_map(&routes, |route: &Route| route.route_id.clone());
         let trip_map = make_map(&trips, |trip: &Trip| trip.trip_id.clone());
+        let mut frequency_map: HashMap<String, Vec<usize>> = HashMap::new();
+        for (index, frequency) in frequencies.iter().flatten().enumerate() {
+            frequency_map
+                .entry(frequency.trip_id.clone())
+                .or_default()
+                .push(index);
+        }
+        let translation_map = match translations {
+            Some(ref translations) => make_map(translations, TranslationKey::from_record),
+            None => HashMap::new(),
+        };
 
         Ok(TransitFeed {
-            agencies: agencies,
-            stops: stops,
-            stop_map: stop_map,
-            routes: routes,
-            route_map: route_map,
-            trips: trips,
-            trip_map: trip_map,
-            stoptimes: stoptimes,
-            calendars: calendars,
-            calendar_dates: load_optional_feed_file(reader.calendar_dates()),
-            fare_attributes: load_optional_feed_file(reader.fare_attributes()),
-            fare_rules: load_optional_feed_file(reader.fare_rules()),
-            shapes: load_optional_feed_file(reader.shapes()),
-            frequencies: load_optional_feed_file(reader.frequencies()),
-            transfers: load_optional_feed_file(reader.transfers()),
-            feedinfo: match load_optional_feed_file(reader.feed_info()) {
-                Some(mut records) => {
-                    if records.len() != 1 {
-                        println!("Unexpected number of entries in feed_info.txt");
-                    }
-                    records.pop()
-                }
-                None => None,
-            },
+            agencies,
+            stops,
+            stop_map,
+            children_map,
+            routes,
+            route_map,
+            trips,
+            trip_map,
+            frequency_map,
+            translation_map,
+            stoptimes,
+            calendars,
+            calendar_dates,
+            fare_attributes,
+            fare_rules,
+            fare_media,
+            fare_products,
+            fare_leg_rules,
+            fare_transfer_rules,
+            areas,
+            stop_areas,
+            networks,
+            route_networks,
+            timeframes,
+            shapes,
+            frequencies,
+            transfers,
+            pathways,
+            levels,
+            translations,
+            attributions,
+            booking_rules,
+            location_groups,
+            location_group_stops,
+            locations,
+            feedinfo,
+            report: loader.report,
+            lines: loader.lines,
         })
     }
 
+    /// Records and files that were skipped or missing when the feed was loaded
+    pub fn load_report(&self) -> &LoadReport {
+        &self.report
+    }
+
+    /// Line in `filename` that the record at `index` was loaded from
+    ///
+    /// Indices match the position of the record in the feed's `Vec` for that file.
+    pub fn record_line(&self, filename: &str, index: usize) -> Option<u64> {
+        self.lines
+            .get(filename)
+            .and_then(|lines| lines.get(index))
+            .cloned()
+    }
+
     pub fn find_stop(&self, id: &str) -> Option<&Stop> {
         TransitFeed::find_record(id, &self.stop_map, &self.stops)
     }
 
+    /// Stops whose parent_station is `parent_id`
+    pub fn children(&self, parent_id: &str) -> Vec<&Stop> {
+        match self.children_map.get(parent_id) {
+            Some(children) => children.iter().map(|index| &self.stops[*index]).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Platforms and stops within `station_id`
+    pub fn platforms(&self, station_id: &str) -> Vec<&Stop> {
+        self.children_of_type(station_id, LocationType::Stop)
+    }
+
+    /// Entrances and exits of `station_id`
+    pub fn entrances(&self, station_id: &str) -> Vec<&Stop> {
+        self.children_of_type(station_id, LocationType::Entrance)
+    }
+
+    /// Boarding areas on `platform_id`
+    pub fn boarding_areas(&self, platform_id: &str) -> Vec<&Stop> {
+        self.children_of_type(platform_id, LocationType::BoardingArea)
+    }
+
+    /// Station that `stop_id` belongs to, a station belongs to itself
+    pub fn station(&self, stop_id: &str) -> Option<&Stop> {
+        let mut stop = self.find_stop(stop_id)?;
+        // boarding areas are two levels down, anything deeper is malformed
+        for _ in 0..3 {
+            if stop.location_type == LocationType::Station {
+                return Some(stop);
+            }
+            stop = self.find_stop(stop.parent_station.as_ref()?)?;
+        }
+        None
+    }
+
+    fn children_of_type(&self, parent_id: &str, location_type: LocationType) -> Vec<&Stop> {
+        self.children(parent_id)
+            .into_iter()
+            .filter(|stop| stop.location_type == location_type)
+            .collect()
+    }
+
     pub fn find_route(&self, id: &str) -> Option<&Route> {
         TransitFeed::find_record(id, &self.route_map, &self.routes)
     }
@@ -247,6 +1163,147 @@ impl TransitFeed {
         TransitFeed::find_record(id, &self.trip_map, &self.trips)
     }
 
+    /// Rows of frequencies.txt for `trip_id`, empty if the trip isn't frequency-based
+    pub fn trip_frequencies(&self, trip_id: &str) -> Vec<&Frequency> {
+        match (self.frequency_map.get(trip_id), self.frequencies.as_ref()) {
+            (Some(indices), Some(frequencies)) => {
+                indices.iter().map(|index| &frequencies[*index]).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Attributions that apply to `trip_id`, including those for the whole feed
+    pub fn trip_attributions(&self, trip_id: &str) -> Vec<&Attribution> {
+        let attributions = match self.attributions {
+            Some(ref attributions) => attributions,
+            None => return vec![],
+        };
+        let trip = self.find_trip(trip_id);
+        let route = trip.and_then(|trip| self.find_route(&trip.route_id));
+        // routes may leave out agency_id when the feed has a single agency
+        let agency_id = match route {
+            Some(route) if route.agency_id.is_some() => route.agency_id.as_deref(),
+            Some(_) if self.agencies.len() == 1 => self.agencies[0].agency_id.as_deref(),
+            _ => None,
+        };
+        attributions
+            .iter()
+            .filter(|attribution| {
+                match (
+                    attribution.agency_id.as_deref(),
+                    attribution.route_id.as_deref(),
+                    attribution.trip_id.as_deref(),
+                ) {
+                    (None, None, None) => true,
+                    (_, _, Some(id)) => trip.is_some() && id == trip_id,
+                    (_, Some(id), _) => route.is_some_and(|route| id == route.route_id),
+                    (Some(id), _, _) => agency_id == Some(id),
+                }
+            })
+            .collect()
+    }
+
+    /// Translation of `field` of the record with id `record_id` in `table`
+    ///
+    /// `table` and `field` are named as in translations.txt, e.g. `"stops"` and
+    /// `"stop_name"`. Translations by record_id are preferred over those by
+    /// field_value. Returns `None` if there is no translation into `language`.
+    pub fn translate(
+        &self,
+        table: &str,
+        field: &str,
+        record_id: &str,
+        language: &str,
+    ) -> Option<&str> {
+        self.find_translation(TranslationKey::record(
+            table, field, language, record_id, None,
+        ))
+        .or_else(|| {
+            let value = self.field_value(table, field, record_id)?;
+            self.find_translation(TranslationKey::value(table, field, language, value))
+        })
+    }
+
+    /// Translation of `field` of a `StopTime`, such as its stop_headsign
+    pub fn translate_stop_time(
+        &self,
+        stop_time: &StopTime,
+        field: &str,
+        language: &str,
+    ) -> Option<&str> {
+        let table = "stop_times";
+        let sub_id = Some(stop_time.stop_sequence.to_string());
+        self.find_translation(TranslationKey::record(
+            table,
+            field,
+            language,
+            &stop_time.trip_id,
+            sub_id,
+        ))
+        .or_else(|| {
+            let value = match field {
+                "stop_headsign" => stop_time.stop_headsign.as_deref()?,
+                _ => return None,
+            };
+            self.find_translation(TranslationKey::value(table, field, language, value))
+        })
+    }
+
+    fn find_translation(&self, key: TranslationKey) -> Option<&str> {
+        let index = self.translation_map.get(&key)?;
+        self.translations
+            .as_ref()
+            .map(|translations| translations[*index].translation.as_str())
+    }
+
+    /// Untranslated value of a translatable field
+    fn field_value(&self, table: &str, field: &str, record_id: &str) -> Option<&str> {
+        match table {
+            "agency" => {
+                let agency = self
+                    .agencies
+                    .iter()
+                    .find(|agency| agency.agency_id.as_deref() == Some(record_id))?;
+                match field {
+                    "agency_name" => Some(&agency.agency_name),
+                    "agency_url" => Some(&agency.agency_url),
+                    "agency_fare_url" => agency.agency_fare_url.as_deref(),
+                    _ => None,
+                }
+            }
+            "stops" => {
+                let stop = self.find_stop(record_id)?;
+                match field {
+                    "stop_name" => stop.stop_name.as_deref(),
+                    "stop_code" => stop.stop_code.as_deref(),
+                    "stop_desc" => stop.stop_desc.as_deref(),
+                    "stop_url" => stop.stop_url.as_deref(),
+                    _ => None,
+                }
+            }
+            "routes" => {
+                let route = self.find_route(record_id)?;
+                match field {
+                    "route_short_name" => Some(&route.route_short_name),
+                    "route_long_name" => Some(&route.route_long_name),
+                    "route_desc" => route.route_desc.as_deref(),
+                    "route_url" => route.route_url.as_deref(),
+                    _ => None,
+                }
+            }
+            "trips" => {
+                let trip = self.find_trip(record_id)?;
+                match field {
+                    "trip_headsign" => trip.trip_headsign.as_deref(),
+                    "trip_short_name" => trip.trip_short_name.as_deref(),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn find_record<'a, T>(
         record_id: &str,
         map: &HashMap<String, usize>,
@@ -256,37 +1313,7 @@ impl TransitFeed {
     }
 }
 
-// TODO: Need to log stuff here
-fn load_feed_file<R, T>(iter: GTFSIterator<R, T>) -> Vec<T>
-where
-    R: std::io::Read,
-    for<'de> T: serde::Deserialize<'de>,
-{
-    iter.filter_map(|r| match r {
-        Ok(r) => Some(r),
-        Err(e) => {
-            println!("SKIPPING - {}", e);
-            None
-        }
-    })
-    .collect()
-}
-
-fn load_optional_feed_file<R, T>(result: Result<GTFSIterator<R, T>, Error>) -> Option<Vec<T>>
-where
-    R: std::io::Read,
-    for<'de> T: serde::Deserialize<'de>,
-{
-    match result {
-        Ok(iter) => Some(load_feed_file(iter)),
-        Err(e) => {
-            println!("SKIPPING optional file - {}", e);
-            None
-        }
-    }
-}
-
-fn make_map<T, F: Fn(&T) -> String>(records: &Vec<T>, key_fn: F) -> HashMap<String, usize> {
+fn make_map<T, K: Eq + Hash, F: Fn(&T) -> K>(records: &Vec<T>, key_fn: F) -> HashMap<K, usize> {
     records
         .iter()
         .enumerate()
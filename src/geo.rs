@@ -0,0 +1,89 @@
+use std::f64::consts::PI;
+
+/// Mean radius of the earth in metres
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/// Walking speed in metres per second used when there is no better estimate
+pub const WALKING_SPEED: f64 = 1.3;
+
+/// Great-circle distance in metres between two WGS84 coordinates
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (lon2 - lon1).to_radians();
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+}
+
+/// `(min_lat, min_lon, max_lat, max_lon)` of a box holding every point within
+/// `radius` metres of `(lat, lon)`, widened to all longitudes near the poles
+pub fn bounding_box(lat: f64, lon: f64, radius: f64) -> (f64, f64, f64, f64) {
+    let angle = radius / EARTH_RADIUS;
+    let dlat = angle.to_degrees();
+    let (min_lat, max_lat) = (lat - dlat, lat + dlat);
+    let ratio = angle.sin() / lat.to_radians().cos();
+    if min_lat <= -90.0 || max_lat >= 90.0 || angle >= PI / 2.0 || ratio >= 1.0 {
+        return (min_lat.max(-90.0), -180.0, max_lat.min(90.0), 180.0);
+    }
+    let dlon = ratio.asin().to_degrees();
+    (min_lat, lon - dlon, max_lat, lon + dlon)
+}
+
+/// Pairs of `(lat, lon)` points within `radius` metres of each other, with their distance
+///
+/// Each pair is listed once, with the lower index first.
+pub fn nearby_pairs(points: &[(f64, f64)], radius: f64) -> Vec<(usize, usize, f64)> {
+    let degrees = (radius / EARTH_RADIUS).to_degrees();
+    let mut order: Vec<usize> = (0..points.len()).collect();
+    order.sort_by(|&a, &b| points[a].0.total_cmp(&points[b].0));
+
+    // only points in a band of latitude can be close enough
+    let mut pairs = vec![];
+    for (i, &a) in order.iter().enumerate() {
+        let (lat, lon) = points[a];
+        for &b in order[i + 1..]
+            .iter()
+            .take_while(|&&b| points[b].0 - lat <= degrees)
+        {
+            let distance = haversine_distance(lat, lon, points[b].0, points[b].1);
+            if distance <= radius {
+                pairs.push((a.min(b), a.max(b), distance));
+            }
+        }
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_haversine_distance() {
+        assert_eq!(0.0, haversine_distance(45.5, -73.6, 45.5, -73.6));
+        // one degree of latitude is about 111km
+        let degree = haversine_distance(45.0, -73.0, 46.0, -73.0);
+        assert!((degree - 111_195.0).abs() < 10.0, "{}", degree);
+        // Montréal to Toronto
+        let distance = haversine_distance(45.5017, -73.5673, 43.6532, -79.3832);
+        assert!((distance - 504_000.0).abs() < 2_000.0, "{}", distance);
+    }
+
+    #[test]
+    fn test_nearby_pairs() {
+        let points = [
+            (45.5, -73.6),
+            (45.501, -73.6),
+            (45.5, -73.601),
+            (46.0, -73.6),
+        ];
+        let mut pairs: Vec<(usize, usize)> = nearby_pairs(&points, 150.0)
+            .into_iter()
+            .map(|(a, b, _)| (a, b))
+            .collect();
+        pairs.sort();
+        // 0-1 are 111m apart, 0-2 78m and 1-2 136m
+        assert_eq!(vec![(0, 1), (0, 2), (1, 2)], pairs);
+        assert_eq!(1, nearby_pairs(&points, 100.0).len());
+    }
+}
@@ -1,16 +1,20 @@
 use gtfs::error::Error;
-use csv::{DeserializeError, DeserializeRecordsIntoIter, ErrorKind, Position, Reader, StringRecord};
+use csv::{DeserializeError, ErrorKind, Position, Reader, StringRecord, StringRecordsIntoIter};
 use serde;
 use std;
+use std::marker::PhantomData;
 
 pub struct GTFSIterator<R, T>
 where
     R: std::io::Read,
     T: serde::de::DeserializeOwned,
 {
-    iter: DeserializeRecordsIntoIter<R, T>,
+    iter: StringRecordsIntoIter<R>,
     headers: StringRecord,
+    has_headers: bool,
     filename: String,
+    line: Option<u64>,
+    record_type: PhantomData<T>,
 }
 
 impl<T> GTFSIterator<std::fs::File, T>
@@ -37,12 +41,20 @@ where
             Err(e) => return Err(Error::Csv(filename.to_string(), e)),
         };
         Ok(GTFSIterator {
-            iter: reader.into_deserialize(),
+            has_headers: reader.has_headers(),
+            iter: reader.into_records(),
             headers: headers,
             filename: filename.to_string(),
+            line: None,
+            record_type: PhantomData,
         })
     }
 
+    /// Line number of the record most recently returned by the iterator
+    pub fn line(&self) -> Option<u64> {
+        self.line
+    }
+
     fn wrap_fielderror(&self, err: &DeserializeError, position: &Option<Position>) -> Error {
         let fieldname = match err.field() {
             Some(field_pos) => Some(match self.headers.get(field_pos as usize) {
@@ -69,18 +81,26 @@ where
     type Item = Result<T, Error>;
 
     fn next(&mut self) -> Option<Result<T, Error>> {
-        match self.iter.next() {
-            Some(r) => Some(match r {
-                Err(e) => match e.into_kind() {
-                    ErrorKind::Deserialize { ref pos, ref err } => {
-                        Err(self.wrap_fielderror(err, pos))
-                    }
-                    k => Err(Error::LineError(String::clone(&self.filename), k)),
-                },
-                Ok(s) => Ok(s),
-            }),
-            None => None,
-        }
+        let result = match self.iter.next() {
+            Some(Ok(record)) => {
+                self.line = record.position().map(|pos| pos.line());
+                let headers = if self.has_headers {
+                    Some(&self.headers)
+                } else {
+                    None
+                };
+                record.deserialize(headers)
+            }
+            Some(Err(e)) => Err(e),
+            None => return None,
+        };
+        Some(match result {
+            Err(e) => match e.into_kind() {
+                ErrorKind::Deserialize { ref pos, ref err } => Err(self.wrap_fielderror(err, pos)),
+                k => Err(Error::LineError(String::clone(&self.filename), k)),
+            },
+            Ok(s) => Ok(s),
+        })
     }
 }
 
@@ -113,6 +133,7 @@ Foo,1.0,0
         let reader = csv::Reader::from_reader(data.as_bytes());
         let mut iter: GTFSIterator<_, Test> = GTFSIterator::new(reader, "test.txt").unwrap();
         assert_eq!(&expected, iter.next().unwrap().as_ref().unwrap());
+        assert_eq!(Some(2), iter.line());
     }
 
     #[test]
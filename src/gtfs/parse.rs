@@ -1,5 +1,5 @@
 use serde;
-use serde::Deserializer;
+use serde::{Deserializer, Serializer};
 use chrono::{Duration, NaiveDate};
 
 pub fn deserialize_dow_field<'de, D>(deserializer: D) -> Result<bool, D::Error>
@@ -14,6 +14,31 @@ where
     }
 }
 
+pub fn deserialize_bool_field<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+    match result {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(serde::de::Error::custom("field was not 0 or 1")),
+    }
+}
+
+/// Parses a field that is 1 for true and 0 or blank for false
+pub fn deserialize_flag_field<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let result: String = serde::Deserialize::deserialize(deserializer)?;
+    match result.trim() {
+        "" | "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(serde::de::Error::custom("field was not 0, 1 or blank")),
+    }
+}
+
 pub fn deserialize_calendardate<'de, D>(deserializer: D) -> Result<NaiveDate, D::Error>
 where
     D: Deserializer<'de>,
@@ -63,13 +88,49 @@ where
     }
 }
 
-//#[test]
-//fn parse_timeoffset_test() {
-//    assert_eq!(parse_timeoffset("01:01:01").unwrap(), TimeOffset::from_hms(1, 1, 1));
-//    assert_eq!(parse_timeoffset("1:01:01").unwrap(), TimeOffset::from_hms(1, 1, 1));
-//    assert_eq!(parse_timeoffset("01:01:01  ").unwrap(), TimeOffset::from_hms(1, 1, 1));
-//    assert_eq!(parse_timeoffset(" 01:01:01  ").unwrap(), TimeOffset::from_hms(1, 1, 1));
-//    assert!(parse_timeoffset(":01:01").is_err());
-//    assert!(parse_timeoffset("ab:01:01").is_err());
-//    assert!(parse_timeoffset("01::01").is_err());
-//}
+pub fn serialize_dow_field<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u32(if *value { 1 } else { 0 })
+}
+
+pub fn serialize_bool_field<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u32(if *value { 1 } else { 0 })
+}
+
+pub fn serialize_calendardate<S>(value: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&value.format("%Y%m%d").to_string())
+}
+
+pub fn serialize_option_calendardate<S>(
+    value: &Option<NaiveDate>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match *value {
+        Some(ref date) => serialize_calendardate(date, serializer),
+        None => serializer.serialize_str(""),
+    }
+}
+
+pub fn serialize_transferduration<S>(
+    value: &Option<Duration>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match *value {
+        Some(ref duration) => serializer.serialize_i64(duration.num_seconds()),
+        None => serializer.serialize_str(""),
+    }
+}
@@ -4,18 +4,49 @@
 
 extern crate chrono;
 extern crate csv;
+extern crate flate2;
+extern crate prost;
+extern crate rayon;
+extern crate rstar;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
 extern crate zip;
 
+#[cfg(test)]
+extern crate chrono_tz;
+
 mod archive;
+mod calendar;
+mod csa;
+mod fare;
 mod transit;
 mod gtfs;
 pub mod feed;
+mod geo;
+mod live;
+mod locations;
+pub mod realtime;
+mod routing;
 mod run;
+mod spatial;
+mod station;
+mod transfers;
+mod validate;
 
 pub use transit::*;
+pub use calendar::ServiceCalendar;
+pub use csa::{ConnectionScan, ProfileEntry};
+pub use fare::{Fare, FareCalculator, FareLeg, Ticket};
+pub use geo::WALKING_SPEED;
 pub use gtfs::{Error, GTFSIterator};
-pub use feed::{FeedReader, TransitFeed, Terminator, Trim};
-pub use run::{Run, RunIterator};
+pub use live::{LiveRun, LiveSchedule, LiveStopTime};
+pub use locations::Location;
+pub use feed::{FeedReader, FeedWriter, LoadPolicy, LoadReport, TransitFeed, Terminator, Trim};
+pub use routing::{Journey, JourneyLeg, Router};
+pub use run::{FlagStopSegment, FrequencyRun, Run, RunIterator};
+pub use spatial::{SpatialIndex, StopFilter};
+pub use station::StationGraph;
+pub use transfers::walking_transfers;
+pub use validate::{validate, Finding, Severity};
@@ -0,0 +1,292 @@
+use chrono::{Duration, NaiveDate, TimeZone};
+use std::collections::HashMap;
+
+use calendar::ServiceCalendar;
+use feed::TransitFeed;
+use realtime::{
+    FeedMessage, StopScheduleRelationship, StopTimeEvent, StopTimeUpdate, TripScheduleRelationship,
+    TripUpdate,
+};
+use run::RunIterator;
+use transit::{StopTime, TimeOffset};
+
+/// Predicted times of a trip's stop on the service day
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveStopTime {
+    pub stop_id: String,
+    pub stop_sequence: u64,
+    /// `None` for added trips, otherwise interpolated between timepoints if needed
+    pub scheduled_arrival: Option<TimeOffset>,
+    pub scheduled_departure: Option<TimeOffset>,
+    /// `None` if there is no prediction, as for trips without an update or skipped stops
+    pub arrival: Option<TimeOffset>,
+    pub departure: Option<TimeOffset>,
+    pub schedule_relationship: StopScheduleRelationship,
+}
+
+/// A trip running on the service day with realtime updates applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveRun {
+    pub trip_id: String,
+    /// Departure from the first stop for runs of frequency-based trips, `None` otherwise
+    ///
+    /// Added trips have the `start_time` of their update, if it has one.
+    pub start_time: Option<TimeOffset>,
+    /// `Canceled` trips keep their scheduled stops without predictions
+    pub schedule_relationship: TripScheduleRelationship,
+    pub stop_times: Vec<LiveStopTime>,
+}
+
+/// The runs of a service day with the `TripUpdate`s of a realtime message applied
+///
+/// A stop's delay carries on to the following stops until another `StopTimeUpdate`
+/// gives new information, passing over skipped stops. `NoData` stops have no
+/// prediction, nor do the stops after them until the next update. Delays are never
+/// propagated back to earlier stops. Frequency-based trips have a run for every
+/// departure, see `Run::departures`, and an update applies to the one whose start time
+/// matches its `start_time`.
+#[derive(Debug)]
+pub struct LiveSchedule {
+    pub date: NaiveDate,
+    /// Scheduled and added runs, ordered by trip_id then start_time
+    pub runs: Vec<LiveRun>,
+}
+
+impl LiveSchedule {
+    /// Applies the trip updates of `message` to the trips of `feed` running on `date`
+    ///
+    /// `tz` is the timezone of the feed's agencies and is used to convert the absolute
+    /// times of `StopTimeEvent`s to times of the service day.
+    pub fn new<Tz: TimeZone>(
+        feed: &TransitFeed,
+        message: &FeedMessage,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> LiveSchedule {
+        let day = date.format("%Y%m%d").to_string();
+        let day_start = TimeOffset::from_seconds(0)
+            .to_datetime(date, tz)
+            .map(|start| start.timestamp());
+        let mut updates: HashMap<(&str, Option<TimeOffset>), &TripUpdate> = HashMap::new();
+        for update in message.trip_updates() {
+            let trip = &update.trip;
+            if trip.start_date.as_ref().is_some_and(|start| *start != day) {
+                continue;
+            }
+            if let Some(ref trip_id) = trip.trip_id {
+                updates.insert((trip_id, start_time(update)), update);
+            }
+        }
+
+        let no_update = TripUpdate::default();
+        let calendar = ServiceCalendar::new(feed);
+        let services = calendar.active_services(date);
+        let mut runs = vec![];
+        for run in RunIterator::new(feed.stoptimes.iter()) {
+            let trip_id = match feed.find_trip(&run.trip) {
+                Some(trip) if services.contains(trip.service_id.as_str()) => trip.trip_id.as_str(),
+                _ => continue,
+            };
+            let stop_times = run
+                .interpolate(feed)
+                .unwrap_or_else(|| run.sequence.iter().map(|&st| st.clone()).collect());
+            for (start_time, stop_times) in run.departures(feed, stop_times) {
+                // updates for scheduled trips may leave out their start_time
+                let first_departure = stop_times.first().and_then(|st| st.departure_time);
+                let update = match start_time {
+                    Some(start) => updates.remove(&(trip_id, Some(start))),
+                    None => updates
+                        .remove(&(trip_id, None))
+                        .or_else(|| updates.remove(&(trip_id, first_departure))),
+                };
+                runs.push(apply_update(
+                    trip_id,
+                    start_time,
+                    &stop_times,
+                    update.unwrap_or(&no_update),
+                    day_start,
+                ));
+            }
+        }
+
+        // whatever is left is for trips that aren't scheduled on the day
+        for ((trip_id, start_time), update) in updates {
+            match update.trip.schedule_relationship() {
+                TripScheduleRelationship::Added | TripScheduleRelationship::New => {
+                    runs.push(added_run(trip_id, start_time, update, day_start))
+                }
+                _ => {}
+            }
+        }
+        runs.sort_by(|a, b| (&a.trip_id, a.start_time).cmp(&(&b.trip_id, b.start_time)));
+        LiveSchedule { date, runs }
+    }
+
+    /// The run of `trip_id` leaving at `start_time`, which is `None` unless the trip is
+    /// frequency-based
+    pub fn run(&self, trip_id: &str, start_time: Option<TimeOffset>) -> Option<&LiveRun> {
+        self.runs
+            .iter()
+            .find(|run| run.trip_id == trip_id && run.start_time == start_time)
+    }
+}
+
+/// The `start_time` of the trip `update` is for, if it has a valid one
+fn start_time(update: &TripUpdate) -> Option<TimeOffset> {
+    update
+        .trip
+        .start_time
+        .as_ref()
+        .and_then(|start_time| start_time.parse().ok())
+}
+
+/// Predicted times of a scheduled run
+fn apply_update(
+    trip_id: &str,
+    start_time: Option<TimeOffset>,
+    stop_times: &[StopTime],
+    update: &TripUpdate,
+    day_start: Option<i64>,
+) -> LiveRun {
+    let trip_relationship = update.trip.schedule_relationship();
+    let canceled = matches!(
+        trip_relationship,
+        TripScheduleRelationship::Canceled | TripScheduleRelationship::Deleted
+    );
+    let mut delay = update.delay.map(i64::from);
+    let stop_times = stop_times
+        .iter()
+        .map(|stop_time| {
+            let stop_update = find_stop_time_update(update, stop_time);
+            let relationship = stop_update
+                .map(|stop_update| stop_update.schedule_relationship())
+                .unwrap_or(StopScheduleRelationship::Scheduled);
+            let (arrival, departure) = if canceled {
+                (None, None)
+            } else {
+                match relationship {
+                    StopScheduleRelationship::NoData => {
+                        delay = None;
+                        (None, None)
+                    }
+                    StopScheduleRelationship::Skipped => (None, None),
+                    _ => {
+                        let predicted_delay = |event: Option<&StopTimeEvent>, scheduled| {
+                            event.and_then(|event| event_delay(event, scheduled, day_start))
+                        };
+                        let arrival_delay = predicted_delay(
+                            stop_update.and_then(|stop_update| stop_update.arrival.as_ref()),
+                            stop_time.arrival_time,
+                        )
+                        .or(delay);
+                        let departure_delay = predicted_delay(
+                            stop_update.and_then(|stop_update| stop_update.departure.as_ref()),
+                            stop_time.departure_time,
+                        )
+                        .or(arrival_delay);
+                        delay = departure_delay;
+                        (
+                            delayed(stop_time.arrival_time, arrival_delay),
+                            delayed(stop_time.departure_time, departure_delay),
+                        )
+                    }
+                }
+            };
+            LiveStopTime {
+                stop_id: stop_time.stop_id.clone(),
+                stop_sequence: stop_time.stop_sequence,
+                scheduled_arrival: stop_time.arrival_time,
+                scheduled_departure: stop_time.departure_time,
+                arrival,
+                departure,
+                schedule_relationship: relationship,
+            }
+        })
+        .collect();
+    LiveRun {
+        trip_id: trip_id.to_string(),
+        start_time,
+        schedule_relationship: if canceled {
+            TripScheduleRelationship::Canceled
+        } else {
+            TripScheduleRelationship::Scheduled
+        },
+        stop_times,
+    }
+}
+
+/// A run for a trip that isn't in the static schedule, taken from its updates
+fn added_run(
+    trip_id: &str,
+    start_time: Option<TimeOffset>,
+    update: &TripUpdate,
+    day_start: Option<i64>,
+) -> LiveRun {
+    let event_time = |event: &Option<StopTimeEvent>| match (
+        event.as_ref().and_then(|event| event.time),
+        day_start,
+    ) {
+        (Some(time), Some(start)) if time >= start => {
+            Some(TimeOffset::from_seconds((time - start) as u32))
+        }
+        _ => None,
+    };
+    LiveRun {
+        trip_id: trip_id.to_string(),
+        start_time,
+        schedule_relationship: TripScheduleRelationship::Added,
+        stop_times: update
+            .stop_time_update
+            .iter()
+            .enumerate()
+            .map(|(index, stop_update)| {
+                let arrival = event_time(&stop_update.arrival);
+                let departure = event_time(&stop_update.departure);
+                LiveStopTime {
+                    stop_id: stop_update.stop_id.clone().unwrap_or_default(),
+                    stop_sequence: stop_update.stop_sequence.map_or(index as u64, u64::from),
+                    scheduled_arrival: None,
+                    scheduled_departure: None,
+                    arrival: arrival.or(departure),
+                    departure: departure.or(arrival),
+                    schedule_relationship: stop_update.schedule_relationship(),
+                }
+            })
+            .collect(),
+    }
+}
+
+/// The update for `stop_time`, matched by stop_sequence if it has one or else stop_id
+fn find_stop_time_update<'a>(
+    update: &'a TripUpdate,
+    stop_time: &StopTime,
+) -> Option<&'a StopTimeUpdate> {
+    update
+        .stop_time_update
+        .iter()
+        .find(|stop_update| match stop_update.stop_sequence {
+            Some(sequence) => u64::from(sequence) == stop_time.stop_sequence,
+            None => stop_update.stop_id.as_ref() == Some(&stop_time.stop_id),
+        })
+}
+
+/// Seconds `event` is behind `scheduled`, negative if it is early
+fn event_delay(
+    event: &StopTimeEvent,
+    scheduled: Option<TimeOffset>,
+    day_start: Option<i64>,
+) -> Option<i64> {
+    match (event.time, scheduled, day_start) {
+        (Some(time), Some(scheduled), Some(start)) => {
+            Some(time - start - i64::from(scheduled.total_seconds()))
+        }
+        _ => event.delay.map(i64::from),
+    }
+}
+
+fn delayed(time: Option<TimeOffset>, delay: Option<i64>) -> Option<TimeOffset> {
+    match (time, delay) {
+        (Some(time), Some(delay)) => Some(time + Duration::seconds(delay)),
+        _ => None,
+    }
+}
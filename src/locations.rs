@@ -0,0 +1,223 @@
+use serde_json;
+use std::io::{Read, Write};
+
+use gtfs::Error;
+
+/// A GTFS-Flex zone from locations.geojson
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub location_id: String,
+    pub stop_name: Option<String>,
+    pub stop_desc: Option<String>,
+    /// Polygons of the zone, each an outer ring followed by any holes
+    ///
+    /// Points are `[longitude, latitude]` as in GeoJSON.
+    pub polygons: Vec<Vec<Vec<[f64; 2]>>>,
+}
+
+impl Location {
+    /// True if the point is inside the zone
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        self.polygons.iter().any(|polygon| {
+            let mut rings = polygon.iter();
+            match rings.next() {
+                Some(outer) => {
+                    ring_contains(outer, lat, lon)
+                        && !rings.any(|hole| ring_contains(hole, lat, lon))
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+/// Even-odd test of whether a point is inside a closed ring
+fn ring_contains(ring: &[[f64; 2]], lat: f64, lon: f64) -> bool {
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        if (a[1] > lat) != (b[1] > lat) && lon < (b[0] - a[0]) * (lat - a[1]) / (b[1] - a[1]) + a[0]
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum FeatureCollection {
+    FeatureCollection { features: Vec<Feature> },
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum Feature {
+    Feature {
+        id: String,
+        #[serde(default)]
+        properties: Properties,
+        geometry: Geometry,
+    },
+}
+
+#[derive(Default, Deserialize, Serialize)]
+struct Properties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_desc: Option<String>,
+}
+
+/// Positions may include an altitude, which is ignored
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type", content = "coordinates")]
+enum Geometry {
+    Polygon(Vec<Vec<Vec<f64>>>),
+    MultiPolygon(Vec<Vec<Vec<Vec<f64>>>>),
+}
+
+fn to_points(ring: Vec<Vec<f64>>) -> Result<Vec<[f64; 2]>, String> {
+    ring.into_iter()
+        .map(|position| match position.as_slice() {
+            [lon, lat, ..] => Ok([*lon, *lat]),
+            _ => Err("positions must have a longitude and latitude".to_string()),
+        })
+        .collect()
+}
+
+/// Reads the locations of a locations.geojson file
+pub fn read_locations<R: Read>(reader: R, source: &str) -> Result<Vec<Location>, Error> {
+    let invalid =
+        |message: String| Error::Feed(format!("invalid GeoJSON in {} - {}", source, message));
+    let FeatureCollection::FeatureCollection { features } =
+        serde_json::from_reader(reader).map_err(|e| invalid(e.to_string()))?;
+    features
+        .into_iter()
+        .map(|feature| {
+            let Feature::Feature {
+                id,
+                properties,
+                geometry,
+            } = feature;
+            let polygons = match geometry {
+                Geometry::Polygon(polygon) => vec![polygon],
+                Geometry::MultiPolygon(polygons) => polygons,
+            };
+            Ok(Location {
+                location_id: id,
+                stop_name: properties.stop_name,
+                stop_desc: properties.stop_desc,
+                polygons: polygons
+                    .into_iter()
+                    .map(|polygon| polygon.into_iter().map(to_points).collect())
+                    .collect::<Result<_, _>>()
+                    .map_err(invalid)?,
+            })
+        })
+        .collect()
+}
+
+/// Writes `locations` as a locations.geojson file
+pub fn write_locations<'a, W, I>(writer: W, locations: I) -> Result<(), Error>
+where
+    W: Write,
+    I: IntoIterator<Item = &'a Location>,
+{
+    let features = locations
+        .into_iter()
+        .map(|location| Feature::Feature {
+            id: location.location_id.clone(),
+            properties: Properties {
+                stop_name: location.stop_name.clone(),
+                stop_desc: location.stop_desc.clone(),
+            },
+            geometry: Geometry::MultiPolygon(
+                location
+                    .polygons
+                    .iter()
+                    .map(|polygon| {
+                        polygon
+                            .iter()
+                            .map(|ring| ring.iter().map(|point| point.to_vec()).collect())
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        })
+        .collect();
+    serde_json::to_writer(writer, &FeatureCollection::FeatureCollection { features })
+        .map_err(|e| Error::Feed(format!("could not write locations.geojson - {}", e)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const LOCATIONS: &str = r#"{
+        "type": "FeatureCollection",
+        "features": [
+            {
+                "type": "Feature",
+                "id": "ZONE",
+                "properties": {"stop_name": "Downtown zone"},
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [
+                        [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0], [0.0, 0.0]],
+                        [[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 2.0], [1.0, 1.0]]
+                    ]
+                }
+            },
+            {
+                "type": "Feature",
+                "id": "ISLANDS",
+                "geometry": {
+                    "type": "MultiPolygon",
+                    "coordinates": [
+                        [[[10.0, 10.0, 5.0], [11.0, 10.0, 5.0], [11.0, 11.0, 5.0], [10.0, 10.0, 5.0]]],
+                        [[[20.0, 20.0], [21.0, 20.0], [21.0, 21.0], [20.0, 20.0]]]
+                    ]
+                }
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_read_locations() {
+        let locations = read_locations(LOCATIONS.as_bytes(), "locations.geojson").unwrap();
+        assert_eq!(2, locations.len());
+        assert_eq!("ZONE", locations[0].location_id);
+        assert_eq!(Some("Downtown zone".to_string()), locations[0].stop_name);
+        assert_eq!(1, locations[0].polygons.len());
+        assert_eq!(2, locations[1].polygons.len());
+        assert_eq!([10.0, 10.0], locations[1].polygons[0][0][0]);
+    }
+
+    #[test]
+    fn test_location_contains() {
+        let locations = read_locations(LOCATIONS.as_bytes(), "locations.geojson").unwrap();
+        assert!(locations[0].contains(3.0, 3.0));
+        // inside the hole
+        assert!(!locations[0].contains(1.5, 1.5));
+        assert!(!locations[0].contains(5.0, 1.0));
+        assert!(locations[1].contains(20.2, 20.8));
+    }
+
+    #[test]
+    fn test_write_locations() {
+        let locations = read_locations(LOCATIONS.as_bytes(), "locations.geojson").unwrap();
+        let mut written = vec![];
+        write_locations(&mut written, &locations).unwrap();
+        assert_eq!(
+            locations,
+            read_locations(&written[..], "locations.geojson").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_locations() {
+        let result = read_locations(&b"{\"type\": \"Point\"}"[..], "zip/locations.geojson");
+        assert!(format!("{}", result.err().unwrap())
+            .starts_with("error in feed - invalid GeoJSON in zip/locations.geojson - "));
+    }
+}
@@ -0,0 +1,658 @@
+//! GTFS-Realtime messages decoded from their protobuf encoding
+//!
+//! The types mirror gtfs-realtime.proto. Enum fields are stored as their numeric
+//! value and read through the generated accessor of the same name, which falls back
+//! to the spec's default for missing or unknown values.
+
+use prost::{Enumeration, Message};
+use std::fs::File;
+use std::io::Read;
+
+use feed::TransitFeed;
+use gtfs::Error;
+use transit::{Route, Stop, Trip};
+
+/// Contents of a GTFS-Realtime feed
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedMessage {
+    #[prost(message, required, tag = "1")]
+    pub header: FeedHeader,
+    #[prost(message, repeated, tag = "2")]
+    pub entity: Vec<FeedEntity>,
+}
+
+impl FeedMessage {
+    /// Decodes a protobuf encoded message
+    pub fn from_bytes(bytes: &[u8]) -> Result<FeedMessage, Error> {
+        FeedMessage::decode(bytes)
+            .map_err(|e| Error::Feed(format!("invalid GTFS-Realtime message - {}", e)))
+    }
+
+    /// Reads and decodes a protobuf encoded message
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<FeedMessage, Error> {
+        let mut bytes = vec![];
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(|e| Error::Feed(format!("{}", e)))?;
+        FeedMessage::from_bytes(&bytes)
+    }
+
+    /// Decodes a message saved to `path`
+    pub fn from_file(path: &str) -> Result<FeedMessage, Error> {
+        let file = File::open(path).map_err(|e| Error::Feed(format!("{}", e)))?;
+        FeedMessage::from_reader(file)
+    }
+
+    /// Trip updates of the entities that haven't been deleted
+    pub fn trip_updates(&self) -> impl Iterator<Item = &TripUpdate> {
+        self.live_entities()
+            .filter_map(|entity| entity.trip_update.as_ref())
+    }
+
+    /// Vehicle positions of the entities that haven't been deleted
+    pub fn vehicle_positions(&self) -> impl Iterator<Item = &VehiclePosition> {
+        self.live_entities()
+            .filter_map(|entity| entity.vehicle.as_ref())
+    }
+
+    /// Alerts of the entities that haven't been deleted
+    pub fn alerts(&self) -> impl Iterator<Item = &Alert> {
+        self.live_entities()
+            .filter_map(|entity| entity.alert.as_ref())
+    }
+
+    fn live_entities(&self) -> impl Iterator<Item = &FeedEntity> {
+        self.entity.iter().filter(|entity| !entity.is_deleted())
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedHeader {
+    #[prost(string, required, tag = "1")]
+    pub gtfs_realtime_version: String,
+    #[prost(
+        enumeration = "Incrementality",
+        optional,
+        tag = "2",
+        default = "FullDataset"
+    )]
+    pub incrementality: Option<i32>,
+    /// POSIX time the content was created
+    #[prost(uint64, optional, tag = "3")]
+    pub timestamp: Option<u64>,
+    #[prost(string, optional, tag = "4")]
+    pub feed_version: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum Incrementality {
+    FullDataset = 0,
+    Differential = 1,
+}
+
+/// An update to a trip, vehicle or alert, only one of which is set
+#[derive(Clone, PartialEq, Message)]
+pub struct FeedEntity {
+    #[prost(string, required, tag = "1")]
+    pub id: String,
+    /// Whether the entity is removed, only in `Incrementality::Differential` feeds
+    #[prost(bool, optional, tag = "2", default = "false")]
+    pub is_deleted: Option<bool>,
+    #[prost(message, optional, tag = "3")]
+    pub trip_update: Option<TripUpdate>,
+    #[prost(message, optional, tag = "4")]
+    pub vehicle: Option<VehiclePosition>,
+    #[prost(message, optional, tag = "5")]
+    pub alert: Option<Alert>,
+}
+
+/// Realtime progress of a trip
+#[derive(Clone, PartialEq, Message)]
+pub struct TripUpdate {
+    #[prost(message, required, tag = "1")]
+    pub trip: TripDescriptor,
+    #[prost(message, optional, tag = "3")]
+    pub vehicle: Option<VehicleDescriptor>,
+    /// Updates ordered by stop sequence
+    #[prost(message, repeated, tag = "2")]
+    pub stop_time_update: Vec<StopTimeUpdate>,
+    #[prost(uint64, optional, tag = "4")]
+    pub timestamp: Option<u64>,
+    /// Delay in seconds for stops without a `StopTimeUpdate`
+    #[prost(int32, optional, tag = "5")]
+    pub delay: Option<i32>,
+}
+
+/// Predicted time of an arrival or departure, either as a delay or an absolute time
+#[derive(Clone, PartialEq, Message)]
+pub struct StopTimeEvent {
+    /// Seconds behind schedule, negative when early
+    #[prost(int32, optional, tag = "1")]
+    pub delay: Option<i32>,
+    /// POSIX time of the event
+    #[prost(int64, optional, tag = "2")]
+    pub time: Option<i64>,
+    #[prost(int32, optional, tag = "3")]
+    pub uncertainty: Option<i32>,
+    #[prost(int64, optional, tag = "4")]
+    pub scheduled_time: Option<i64>,
+}
+
+/// Realtime update for one stop of a trip, matched by `stop_sequence` or `stop_id`
+#[derive(Clone, PartialEq, Message)]
+pub struct StopTimeUpdate {
+    #[prost(uint32, optional, tag = "1")]
+    pub stop_sequence: Option<u32>,
+    #[prost(string, optional, tag = "4")]
+    pub stop_id: Option<String>,
+    #[prost(message, optional, tag = "2")]
+    pub arrival: Option<StopTimeEvent>,
+    #[prost(message, optional, tag = "3")]
+    pub departure: Option<StopTimeEvent>,
+    #[prost(
+        enumeration = "StopScheduleRelationship",
+        optional,
+        tag = "5",
+        default = "Scheduled"
+    )]
+    pub schedule_relationship: Option<i32>,
+}
+
+impl StopTimeUpdate {
+    /// The stop the update is for, if it is given and exists in `feed`
+    pub fn stop<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Stop> {
+        self.stop_id.as_ref().and_then(|id| feed.find_stop(id))
+    }
+}
+
+/// ScheduleRelationship of a `StopTimeUpdate`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum StopScheduleRelationship {
+    Scheduled = 0,
+    /// The vehicle won't stop here
+    Skipped = 1,
+    /// There is no realtime information for the stop
+    NoData = 2,
+    Unscheduled = 3,
+}
+
+/// Identifies the trip a realtime update is about
+#[derive(Clone, PartialEq, Message)]
+pub struct TripDescriptor {
+    #[prost(string, optional, tag = "1")]
+    pub trip_id: Option<String>,
+    #[prost(string, optional, tag = "5")]
+    pub route_id: Option<String>,
+    #[prost(uint32, optional, tag = "6")]
+    pub direction_id: Option<u32>,
+    /// Start time of the trip as HH:MM:SS, for frequency-based trips
+    #[prost(string, optional, tag = "2")]
+    pub start_time: Option<String>,
+    /// Service date of the trip as YYYYMMDD
+    #[prost(string, optional, tag = "3")]
+    pub start_date: Option<String>,
+    #[prost(
+        enumeration = "TripScheduleRelationship",
+        optional,
+        tag = "4",
+        default = "Scheduled"
+    )]
+    pub schedule_relationship: Option<i32>,
+}
+
+impl TripDescriptor {
+    /// The trip described, if it is given and exists in `feed`
+    pub fn trip<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Trip> {
+        self.trip_id.as_ref().and_then(|id| feed.find_trip(id))
+    }
+
+    /// The route described, or else the route of the trip
+    pub fn route<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Route> {
+        match self.route_id {
+            Some(ref route_id) => feed.find_route(route_id),
+            None => self
+                .trip(feed)
+                .and_then(|trip| feed.find_route(&trip.route_id)),
+        }
+    }
+}
+
+/// ScheduleRelationship of a `TripDescriptor`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum TripScheduleRelationship {
+    Scheduled = 0,
+    /// An extra trip that isn't in the static feed
+    Added = 1,
+    Unscheduled = 2,
+    Canceled = 3,
+    Replacement = 5,
+    Duplicated = 6,
+    Deleted = 7,
+    New = 8,
+}
+
+/// Identifies the vehicle running a trip
+#[derive(Clone, PartialEq, Message)]
+pub struct VehicleDescriptor {
+    #[prost(string, optional, tag = "1")]
+    pub id: Option<String>,
+    /// Label shown to riders
+    #[prost(string, optional, tag = "2")]
+    pub label: Option<String>,
+    #[prost(string, optional, tag = "3")]
+    pub license_plate: Option<String>,
+}
+
+/// Realtime position of a vehicle
+#[derive(Clone, PartialEq, Message)]
+pub struct VehiclePosition {
+    #[prost(message, optional, tag = "1")]
+    pub trip: Option<TripDescriptor>,
+    #[prost(message, optional, tag = "8")]
+    pub vehicle: Option<VehicleDescriptor>,
+    #[prost(message, optional, tag = "2")]
+    pub position: Option<Position>,
+    #[prost(uint32, optional, tag = "3")]
+    pub current_stop_sequence: Option<u32>,
+    #[prost(string, optional, tag = "7")]
+    pub stop_id: Option<String>,
+    #[prost(
+        enumeration = "VehicleStopStatus",
+        optional,
+        tag = "4",
+        default = "InTransitTo"
+    )]
+    pub current_status: Option<i32>,
+    #[prost(uint64, optional, tag = "5")]
+    pub timestamp: Option<u64>,
+    #[prost(enumeration = "CongestionLevel", optional, tag = "6")]
+    pub congestion_level: Option<i32>,
+    #[prost(enumeration = "OccupancyStatus", optional, tag = "9")]
+    pub occupancy_status: Option<i32>,
+    #[prost(uint32, optional, tag = "10")]
+    pub occupancy_percentage: Option<u32>,
+}
+
+impl VehiclePosition {
+    /// The stop the vehicle is at or heading to, if it is given and exists in `feed`
+    pub fn stop<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Stop> {
+        self.stop_id.as_ref().and_then(|id| feed.find_stop(id))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum VehicleStopStatus {
+    IncomingAt = 0,
+    StoppedAt = 1,
+    InTransitTo = 2,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum CongestionLevel {
+    UnknownCongestionLevel = 0,
+    RunningSmoothly = 1,
+    StopAndGo = 2,
+    Congestion = 3,
+    SevereCongestion = 4,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum OccupancyStatus {
+    Empty = 0,
+    ManySeatsAvailable = 1,
+    FewSeatsAvailable = 2,
+    StandingRoomOnly = 3,
+    CrushedStandingRoomOnly = 4,
+    Full = 5,
+    NotAcceptingPassengers = 6,
+    NoDataAvailable = 7,
+    NotBoardable = 8,
+}
+
+/// WGS84 position of a vehicle
+#[derive(Clone, PartialEq, Message)]
+pub struct Position {
+    #[prost(float, required, tag = "1")]
+    pub latitude: f32,
+    #[prost(float, required, tag = "2")]
+    pub longitude: f32,
+    /// Degrees clockwise from north
+    #[prost(float, optional, tag = "3")]
+    pub bearing: Option<f32>,
+    /// Metres travelled by the vehicle
+    #[prost(double, optional, tag = "4")]
+    pub odometer: Option<f64>,
+    /// Metres per second
+    #[prost(float, optional, tag = "5")]
+    pub speed: Option<f32>,
+}
+
+/// A service alert for the agencies, routes, trips or stops in `informed_entity`
+#[derive(Clone, PartialEq, Message)]
+pub struct Alert {
+    #[prost(message, repeated, tag = "1")]
+    pub active_period: Vec<TimeRange>,
+    #[prost(message, repeated, tag = "5")]
+    pub informed_entity: Vec<EntitySelector>,
+    #[prost(enumeration = "Cause", optional, tag = "6", default = "UnknownCause")]
+    pub cause: Option<i32>,
+    #[prost(enumeration = "Effect", optional, tag = "7", default = "UnknownEffect")]
+    pub effect: Option<i32>,
+    #[prost(message, optional, tag = "8")]
+    pub url: Option<TranslatedString>,
+    #[prost(message, optional, tag = "10")]
+    pub header_text: Option<TranslatedString>,
+    #[prost(message, optional, tag = "11")]
+    pub description_text: Option<TranslatedString>,
+    #[prost(
+        enumeration = "SeverityLevel",
+        optional,
+        tag = "14",
+        default = "UnknownSeverity"
+    )]
+    pub severity_level: Option<i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum Cause {
+    UnknownCause = 1,
+    OtherCause = 2,
+    TechnicalProblem = 3,
+    Strike = 4,
+    Demonstration = 5,
+    Accident = 6,
+    Holiday = 7,
+    Weather = 8,
+    Maintenance = 9,
+    Construction = 10,
+    PoliceActivity = 11,
+    MedicalEmergency = 12,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum Effect {
+    NoService = 1,
+    ReducedService = 2,
+    SignificantDelays = 3,
+    Detour = 4,
+    AdditionalService = 5,
+    ModifiedService = 6,
+    OtherEffect = 7,
+    UnknownEffect = 8,
+    StopMoved = 9,
+    NoEffect = 10,
+    AccessibilityIssue = 11,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Enumeration)]
+#[repr(i32)]
+pub enum SeverityLevel {
+    UnknownSeverity = 1,
+    Info = 2,
+    Warning = 3,
+    Severe = 4,
+}
+
+/// Interval in POSIX time, open ended if `start` or `end` is missing
+#[derive(Clone, PartialEq, Message)]
+pub struct TimeRange {
+    #[prost(uint64, optional, tag = "1")]
+    pub start: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub end: Option<u64>,
+}
+
+/// Selects what an `Alert` applies to, every field given must match
+#[derive(Clone, PartialEq, Message)]
+pub struct EntitySelector {
+    #[prost(string, optional, tag = "1")]
+    pub agency_id: Option<String>,
+    #[prost(string, optional, tag = "2")]
+    pub route_id: Option<String>,
+    #[prost(int32, optional, tag = "3")]
+    pub route_type: Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub trip: Option<TripDescriptor>,
+    #[prost(string, optional, tag = "5")]
+    pub stop_id: Option<String>,
+    #[prost(uint32, optional, tag = "6")]
+    pub direction_id: Option<u32>,
+}
+
+impl EntitySelector {
+    /// The route selected, if it is given and exists in `feed`
+    pub fn route<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Route> {
+        self.route_id.as_ref().and_then(|id| feed.find_route(id))
+    }
+
+    /// The trip selected, if it is given and exists in `feed`
+    pub fn trip<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Trip> {
+        self.trip.as_ref().and_then(|trip| trip.trip(feed))
+    }
+
+    /// The stop selected, if it is given and exists in `feed`
+    pub fn stop<'a>(&self, feed: &'a TransitFeed) -> Option<&'a Stop> {
+        self.stop_id.as_ref().and_then(|id| feed.find_stop(id))
+    }
+}
+
+/// Text in several languages
+#[derive(Clone, PartialEq, Message)]
+pub struct TranslatedString {
+    #[prost(message, repeated, tag = "1")]
+    pub translation: Vec<TranslatedText>,
+}
+
+impl TranslatedString {
+    /// Text in `language`, or else the text without a language
+    pub fn text(&self, language: &str) -> Option<&str> {
+        self.translation
+            .iter()
+            .find(|text| text.language.as_deref() == Some(language))
+            .or_else(|| self.translation.iter().find(|text| text.language.is_none()))
+            .map(|text| text.text.as_str())
+    }
+}
+
+/// Translation of a `TranslatedString`
+#[derive(Clone, PartialEq, Message)]
+pub struct TranslatedText {
+    #[prost(string, required, tag = "1")]
+    pub text: String,
+    /// BCP-47 language code
+    #[prost(string, optional, tag = "2")]
+    pub language: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    fn trip(trip_id: &str) -> TripDescriptor {
+        TripDescriptor {
+            trip_id: Some(trip_id.to_string()),
+            ..TripDescriptor::default()
+        }
+    }
+
+    fn message() -> FeedMessage {
+        let mut canceled = trip("T2");
+        canceled.set_schedule_relationship(TripScheduleRelationship::Canceled);
+        let mut skipped = StopTimeUpdate {
+            stop_sequence: Some(2),
+            stop_id: Some("B".to_string()),
+            ..StopTimeUpdate::default()
+        };
+        skipped.set_schedule_relationship(StopScheduleRelationship::Skipped);
+        FeedMessage {
+            header: FeedHeader {
+                gtfs_realtime_version: "2.0".to_string(),
+                timestamp: Some(1_700_000_000),
+                ..FeedHeader::default()
+            },
+            entity: vec![
+                FeedEntity {
+                    id: "1".to_string(),
+                    trip_update: Some(TripUpdate {
+                        trip: trip("T"),
+                        stop_time_update: vec![
+                            StopTimeUpdate {
+                                stop_sequence: Some(1),
+                                arrival: Some(StopTimeEvent {
+                                    delay: Some(120),
+                                    ..StopTimeEvent::default()
+                                }),
+                                ..StopTimeUpdate::default()
+                            },
+                            skipped,
+                        ],
+                        ..TripUpdate::default()
+                    }),
+                    ..FeedEntity::default()
+                },
+                FeedEntity {
+                    id: "2".to_string(),
+                    is_deleted: Some(true),
+                    trip_update: Some(TripUpdate {
+                        trip: canceled,
+                        ..TripUpdate::default()
+                    }),
+                    ..FeedEntity::default()
+                },
+                FeedEntity {
+                    id: "3".to_string(),
+                    vehicle: Some(VehiclePosition {
+                        trip: Some(trip("T")),
+                        position: Some(Position {
+                            latitude: 1.5,
+                            longitude: 2.5,
+                            ..Position::default()
+                        }),
+                        ..VehiclePosition::default()
+                    }),
+                    ..FeedEntity::default()
+                },
+                FeedEntity {
+                    id: "4".to_string(),
+                    alert: Some(Alert {
+                        informed_entity: vec![EntitySelector {
+                            stop_id: Some("A".to_string()),
+                            ..EntitySelector::default()
+                        }],
+                        header_text: Some(TranslatedString {
+                            translation: vec![
+                                TranslatedText {
+                                    text: "Stop closed".to_string(),
+                                    language: None,
+                                },
+                                TranslatedText {
+                                    text: "Arrêt fermé".to_string(),
+                                    language: Some("fr".to_string()),
+                                },
+                            ],
+                        }),
+                        ..Alert::default()
+                    }),
+                    ..FeedEntity::default()
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_decode_feed_message() {
+        let bytes = message().encode_to_vec();
+        let decoded = FeedMessage::from_bytes(&bytes).unwrap();
+        assert_eq!(message(), decoded);
+        assert_eq!(Incrementality::FullDataset, decoded.header.incrementality());
+
+        let updates: Vec<_> = decoded.trip_updates().collect();
+        assert_eq!(1, updates.len());
+        assert_eq!(
+            TripScheduleRelationship::Scheduled,
+            updates[0].trip.schedule_relationship()
+        );
+        assert_eq!(
+            StopScheduleRelationship::Skipped,
+            updates[0].stop_time_update[1].schedule_relationship()
+        );
+        assert_eq!(
+            Some(120),
+            updates[0].stop_time_update[0]
+                .arrival
+                .as_ref()
+                .and_then(|arrival| arrival.delay)
+        );
+
+        let vehicles: Vec<_> = decoded.vehicle_positions().collect();
+        assert_eq!(VehicleStopStatus::InTransitTo, vehicles[0].current_status());
+
+        let alerts: Vec<_> = decoded.alerts().collect();
+        assert_eq!(Effect::UnknownEffect, alerts[0].effect());
+        let header = alerts[0].header_text.as_ref().unwrap();
+        assert_eq!(Some("Arrêt fermé"), header.text("fr"));
+        assert_eq!(Some("Stop closed"), header.text("de"));
+    }
+
+    #[test]
+    fn test_decode_invalid_message() {
+        let result = FeedMessage::from_bytes(b"\x0a\x05oops");
+        assert!(format!("{}", result.err().unwrap())
+            .starts_with("error in feed - invalid GTFS-Realtime message - "));
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let provider: MemoryFeedProvider = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon\nA,Alpha,1.0,2.0\nB,Beta,1.5,2.5\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\nR,1,One,3\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR,S,T\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,A,1\n\
+                 T,08:10:00,08:10:00,B,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+        let message = message();
+
+        let update = message.trip_updates().next().unwrap();
+        assert_eq!("T", update.trip.trip(&feed).unwrap().trip_id);
+        assert_eq!("R", update.trip.route(&feed).unwrap().route_id);
+        assert!(update.stop_time_update[0].stop(&feed).is_none());
+        assert_eq!("B", update.stop_time_update[1].stop(&feed).unwrap().stop_id);
+        assert!(trip("MISSING").trip(&feed).is_none());
+
+        let selector = &message.alerts().next().unwrap().informed_entity[0];
+        assert_eq!("A", selector.stop(&feed).unwrap().stop_id);
+        assert!(selector.route(&feed).is_none());
+    }
+}
@@ -0,0 +1,676 @@
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+
+use calendar::ServiceCalendar;
+use feed::TransitFeed;
+use geo::WALKING_SPEED;
+use run::RunIterator;
+use transfers::nearby_walks;
+use transit::{StopServiceType, TimeOffset, TransferType, Trip};
+
+/// Most trips a journey can take
+const MAX_RIDES: usize = 8;
+
+/// Part of a `Journey`
+#[derive(Debug, Clone, PartialEq)]
+pub enum JourneyLeg {
+    Ride {
+        trip_id: String,
+        route_id: String,
+        from_stop_id: String,
+        to_stop_id: String,
+        departure: TimeOffset,
+        arrival: TimeOffset,
+    },
+    Walk {
+        from_stop_id: String,
+        to_stop_id: String,
+        duration: Duration,
+    },
+}
+
+/// A way of travelling between two stops found by `Router::journeys`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Journey {
+    /// Latest time to leave the origin and still make the first ride
+    pub departure: TimeOffset,
+    pub arrival: TimeOffset,
+    pub legs: Vec<JourneyLeg>,
+}
+
+impl Journey {
+    /// Changes between trips
+    pub fn transfers(&self) -> usize {
+        self.legs
+            .iter()
+            .filter(|leg| match **leg {
+                JourneyLeg::Ride { .. } => true,
+                JourneyLeg::Walk { .. } => false,
+            })
+            .count()
+            .saturating_sub(1)
+    }
+}
+
+/// A scheduled trip of a `Pattern`, with times in seconds of the service day
+struct PatternTrip<'a> {
+    trip: &'a Trip,
+    arrivals: Vec<u32>,
+    departures: Vec<u32>,
+    pickup: Vec<bool>,
+    drop_off: Vec<bool>,
+}
+
+/// Trips of a route that serve the same sequence of stops
+struct Pattern<'a> {
+    route_id: &'a str,
+    stops: Vec<usize>,
+    trips: Vec<PatternTrip<'a>>,
+}
+
+/// How a stop was reached in a round of `Router::journeys`
+#[derive(Clone, Copy)]
+enum Label {
+    Origin,
+    Ride {
+        pattern: usize,
+        trip: usize,
+        board: usize,
+        alight: usize,
+    },
+    Walk {
+        from: usize,
+        seconds: u32,
+    },
+}
+
+/// Numbers the stops of a feed so per-stop state can be kept in vectors
+pub struct StopIndex<'a> {
+    pub ids: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+}
+
+impl<'a> StopIndex<'a> {
+    pub fn new(feed: &'a TransitFeed) -> StopIndex<'a> {
+        let ids: Vec<&str> = feed
+            .stops
+            .iter()
+            .map(|stop| stop.stop_id.as_str())
+            .collect();
+        let index = ids
+            .iter()
+            .enumerate()
+            .map(|(i, &stop_id)| (stop_id, i))
+            .collect();
+        StopIndex { ids, index }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn get(&self, stop_id: &str) -> Option<usize> {
+        self.index.get(stop_id).cloned()
+    }
+
+    /// Index of `stop_id` and of the platforms of it if it is a station
+    pub fn group(&self, feed: &TransitFeed, stop_id: &str) -> Vec<usize> {
+        let mut group: Vec<usize> = self.get(stop_id).into_iter().collect();
+        group.extend(
+            feed.platforms(stop_id)
+                .iter()
+                .filter_map(|stop| self.get(&stop.stop_id)),
+        );
+        group
+    }
+}
+
+/// Footpaths and changes allowed by transfers.txt
+///
+/// `NotPossible` between two stops removes the footpath and at a single stop forbids
+/// changing trips there. Other transfer types between two stops are footpaths taking
+/// `min_transfer_time`, or no time if it is blank, and at a single stop set the time
+/// needed to change trips.
+pub struct Footpaths {
+    walks: Vec<Vec<(usize, u32)>>,
+    /// Seconds needed to change trips at a stop, `None` if changing isn't possible
+    change_times: HashMap<usize, Option<u32>>,
+}
+
+impl Footpaths {
+    pub fn new(feed: &TransitFeed, stops: &StopIndex) -> Footpaths {
+        let mut footpaths = Footpaths {
+            walks: vec![vec![]; stops.len()],
+            change_times: HashMap::new(),
+        };
+        let mut forbidden = HashSet::new();
+        for transfer in feed.transfers.iter().flatten() {
+            let (from, to) = match (
+                stops.get(&transfer.from_stop_id),
+                stops.get(&transfer.to_stop_id),
+            ) {
+                (Some(from), Some(to)) => (from, to),
+                _ => continue,
+            };
+            let seconds = transfer
+                .min_transfer_time
+                .map_or(0, |time| time.num_seconds().max(0) as u32);
+            match transfer.transfer_type {
+                TransferType::NotPossible if from == to => {
+                    footpaths.change_times.insert(from, None);
+                }
+                TransferType::NotPossible => {
+                    forbidden.insert((from, to));
+                }
+                _ if from == to => {
+                    footpaths.change_times.insert(from, Some(seconds));
+                }
+                _ => footpaths.walks[from].push((to, seconds)),
+            }
+        }
+        for (from, walks) in footpaths.walks.iter_mut().enumerate() {
+            walks.retain(|&(to, _)| !forbidden.contains(&(from, to)));
+        }
+        footpaths
+    }
+
+    /// Adds footpaths both ways between stops within `radius` metres of each other
+    ///
+    /// Walks are timed by distance at `WALKING_SPEED`, rounded up to the second. Stops
+    /// already joined by transfers.txt keep their footpath and `NotPossible` transfers
+    /// are respected, including those between the stops' stations.
+    pub fn add_nearby(&mut self, feed: &TransitFeed, stops: &StopIndex, radius: f64) {
+        for (from, to, seconds) in nearby_walks(feed, radius, WALKING_SPEED) {
+            if let (Some(from), Some(to)) = (stops.get(&from.stop_id), stops.get(&to.stop_id)) {
+                self.walks[from].push((to, seconds));
+            }
+        }
+    }
+
+    /// Footpaths leaving `stop` and the seconds they take
+    pub fn walks(&self, stop: usize) -> &[(usize, u32)] {
+        &self.walks[stop]
+    }
+
+    /// Earliest time a rider arriving at `stop` by a vehicle at `arrival` can board another
+    pub fn change(&self, stop: usize, arrival: u32) -> Option<u32> {
+        match self.change_times.get(&stop) {
+            Some(&Some(seconds)) => Some(arrival + seconds),
+            Some(&None) => None,
+            None => Some(arrival),
+        }
+    }
+}
+
+/// Journey planner using the RAPTOR algorithm
+///
+/// Trips are grouped into patterns of trips of a route serving the same stops, with a
+/// trip for every departure of a frequency-based trip. Each round of a query rides one
+/// more trip from the stops improved by the last, then walks the footpaths of
+/// transfers.txt, giving the earliest arrival for every number of rides.
+pub struct Router<'a> {
+    feed: &'a TransitFeed,
+    calendar: ServiceCalendar,
+    stops: StopIndex<'a>,
+    patterns: Vec<Pattern<'a>>,
+    /// Patterns serving each stop and the stop's position in them
+    stop_patterns: Vec<Vec<(usize, usize)>>,
+    footpaths: Footpaths,
+}
+
+impl<'a> Router<'a> {
+    pub fn new(feed: &'a TransitFeed) -> Router<'a> {
+        let stops = StopIndex::new(feed);
+
+        let mut grouped: HashMap<(&str, Vec<usize>), Vec<PatternTrip>> = HashMap::new();
+        for run in RunIterator::new(feed.stoptimes.iter()) {
+            let trip = match feed.find_trip(&run.trip) {
+                Some(trip) => trip,
+                None => continue,
+            };
+            // runs without times at both ends can't be used
+            let stop_times = match run.interpolate(feed) {
+                Some(stop_times) => stop_times,
+                None => continue,
+            };
+            let pattern_stops: Option<Vec<usize>> = stop_times
+                .iter()
+                .map(|stop_time| stops.get(&stop_time.stop_id))
+                .collect();
+            let pattern_stops = match pattern_stops {
+                Some(pattern_stops) => pattern_stops,
+                None => continue,
+            };
+            let seconds = |time: Option<TimeOffset>| time.map_or(0, |time| time.total_seconds());
+            let trips = grouped
+                .entry((trip.route_id.as_str(), pattern_stops))
+                .or_default();
+            for (_, stop_times) in run.departures(feed, stop_times) {
+                trips.push(PatternTrip {
+                    trip,
+                    arrivals: stop_times
+                        .iter()
+                        .map(|st| seconds(st.arrival_time))
+                        .collect(),
+                    departures: stop_times
+                        .iter()
+                        .map(|st| seconds(st.departure_time))
+                        .collect(),
+                    pickup: stop_times
+                        .iter()
+                        .map(|st| st.pickup_type != StopServiceType::NoServiceAvailable)
+                        .collect(),
+                    drop_off: stop_times
+                        .iter()
+                        .map(|st| st.dropoff_type != StopServiceType::NoServiceAvailable)
+                        .collect(),
+                });
+            }
+        }
+
+        let mut patterns = vec![];
+        let mut stop_patterns = vec![vec![]; stops.len()];
+        for ((route_id, pattern_stops), mut trips) in grouped {
+            trips.sort_by_key(|trip| trip.departures[0]);
+            for (position, &stop) in pattern_stops.iter().enumerate() {
+                stop_patterns[stop].push((patterns.len(), position));
+            }
+            patterns.push(Pattern {
+                route_id,
+                stops: pattern_stops,
+                trips,
+            });
+        }
+
+        let footpaths = Footpaths::new(feed, &stops);
+        Router {
+            feed,
+            calendar: ServiceCalendar::new(feed),
+            stops,
+            patterns,
+            stop_patterns,
+            footpaths,
+        }
+    }
+
+    /// Pareto-optimal journeys from `from` to `to` leaving at `departure` on `date`
+    ///
+    /// Each journey arrives earlier than the ones with fewer transfers, which come
+    /// first. A station can be given for either stop to use any of its platforms.
+    pub fn journeys(
+        &self,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+        departure: TimeOffset,
+    ) -> Vec<Journey> {
+        let origins = self.stops.group(self.feed, from);
+        let targets = self.stops.group(self.feed, to);
+        if origins.is_empty() || targets.is_empty() {
+            return vec![];
+        }
+        let services = self.calendar.active_services(date);
+        let start = departure.total_seconds();
+        let stop_count = self.stops.len();
+
+        let mut best = vec![u32::MAX; stop_count];
+        let mut labels: Vec<Option<(u32, Label)>> = vec![None; stop_count];
+        let mut marked = vec![false; stop_count];
+        for &origin in &origins {
+            labels[origin] = Some((start, Label::Origin));
+            best[origin] = start;
+            marked[origin] = true;
+        }
+        self.walk(&origins, &mut labels, &mut best, &mut marked, u32::MAX);
+
+        let mut rounds = vec![labels];
+        let mut journeys = vec![];
+        let mut best_target = u32::MAX;
+        loop {
+            let round = rounds.len() - 1;
+            if let Some((arrival, target)) = targets
+                .iter()
+                .filter_map(|&target| rounds[round][target].map(|(arrival, _)| (arrival, target)))
+                .min()
+            {
+                if arrival < best_target {
+                    best_target = arrival;
+                    journeys.push(self.journey(&rounds, target));
+                }
+            }
+            if round == MAX_RIDES || !marked.iter().any(|&marked| marked) {
+                break;
+            }
+
+            // scan each pattern from the first stop improved in the last round
+            let mut queue: HashMap<usize, usize> = HashMap::new();
+            for stop in (0..stop_count).filter(|&stop| marked[stop]) {
+                for &(pattern, position) in &self.stop_patterns[stop] {
+                    let first = queue.entry(pattern).or_insert(position);
+                    *first = (*first).min(position);
+                }
+            }
+            marked = vec![false; stop_count];
+
+            let previous = &rounds[round];
+            let mut labels = previous.clone();
+            for (index, first) in queue {
+                let pattern = &self.patterns[index];
+                let mut boarded: Option<(usize, usize)> = None;
+                for position in first..pattern.stops.len() {
+                    let stop = pattern.stops[position];
+                    if let Some((trip, board)) = boarded {
+                        let trip_times = &pattern.trips[trip];
+                        let arrival = trip_times.arrivals[position];
+                        if trip_times.drop_off[position] && arrival < best[stop].min(best_target) {
+                            labels[stop] = Some((
+                                arrival,
+                                Label::Ride {
+                                    pattern: index,
+                                    trip,
+                                    board,
+                                    alight: position,
+                                },
+                            ));
+                            best[stop] = arrival;
+                            marked[stop] = true;
+                        }
+                    }
+                    let ready = match previous[stop] {
+                        Some((arrival, Label::Ride { .. })) => self.footpaths.change(stop, arrival),
+                        Some((arrival, _)) => Some(arrival),
+                        None => None,
+                    };
+                    if let Some(ready) = ready {
+                        let current = boarded.map_or(u32::MAX, |(trip, _)| {
+                            pattern.trips[trip].departures[position]
+                        });
+                        let earlier = pattern
+                            .trips
+                            .iter()
+                            .enumerate()
+                            .filter(|&(_, trip)| {
+                                trip.pickup[position]
+                                    && trip.departures[position] >= ready
+                                    && trip.departures[position] < current
+                                    && services.contains(trip.trip.service_id.as_str())
+                            })
+                            .min_by_key(|&(_, trip)| trip.departures[position]);
+                        if let Some((trip, _)) = earlier {
+                            boarded = Some((trip, position));
+                        }
+                    }
+                }
+            }
+
+            let ridden: Vec<usize> = (0..stop_count).filter(|&stop| marked[stop]).collect();
+            self.walk(&ridden, &mut labels, &mut best, &mut marked, best_target);
+            rounds.push(labels);
+        }
+        journeys
+    }
+
+    /// Walks the footpaths leaving `sources`, keeping any arrival that improves on `best`
+    fn walk(
+        &self,
+        sources: &[usize],
+        labels: &mut [Option<(u32, Label)>],
+        best: &mut [u32],
+        marked: &mut [bool],
+        bound: u32,
+    ) {
+        let departures: Vec<(usize, u32)> = sources
+            .iter()
+            .filter_map(|&from| labels[from].map(|(arrival, _)| (from, arrival)))
+            .collect();
+        for (from, departure) in departures {
+            for &(to, seconds) in self.footpaths.walks(from) {
+                let arrival = departure + seconds;
+                if arrival < best[to].min(bound) {
+                    labels[to] = Some((arrival, Label::Walk { from, seconds }));
+                    best[to] = arrival;
+                    marked[to] = true;
+                }
+            }
+        }
+    }
+
+    /// Follows the labels of the last round back from `target` to an origin
+    fn journey(&self, rounds: &[Vec<Option<(u32, Label)>>], target: usize) -> Journey {
+        let mut legs = vec![];
+        let mut round = rounds.len() - 1;
+        let mut stop = target;
+        let arrival = rounds[round][target].map_or(0, |(arrival, _)| arrival);
+        while let Some((_, label)) = rounds[round][stop] {
+            match label {
+                Label::Origin => break,
+                Label::Walk { from, seconds } => {
+                    legs.push(JourneyLeg::Walk {
+                        from_stop_id: self.stops.ids[from].to_string(),
+                        to_stop_id: self.stops.ids[stop].to_string(),
+                        duration: Duration::seconds(seconds as i64),
+                    });
+                    stop = from;
+                }
+                Label::Ride {
+                    pattern,
+                    trip,
+                    board,
+                    alight,
+                } => {
+                    let pattern = &self.patterns[pattern];
+                    let trip = &pattern.trips[trip];
+                    legs.push(JourneyLeg::Ride {
+                        trip_id: trip.trip.trip_id.clone(),
+                        route_id: pattern.route_id.to_string(),
+                        from_stop_id: self.stops.ids[pattern.stops[board]].to_string(),
+                        to_stop_id: self.stops.ids[pattern.stops[alight]].to_string(),
+                        departure: TimeOffset::from_seconds(trip.departures[board]),
+                        arrival: TimeOffset::from_seconds(trip.arrivals[alight]),
+                    });
+                    stop = pattern.stops[board];
+                    round -= 1;
+                }
+            }
+        }
+        legs.reverse();
+
+        // leave in time to walk to the first ride
+        let mut departure = arrival;
+        let mut walking = 0;
+        for leg in &legs {
+            match *leg {
+                JourneyLeg::Walk { duration, .. } => walking += duration.num_seconds() as u32,
+                JourneyLeg::Ride {
+                    departure: ride, ..
+                } => {
+                    departure = ride.total_seconds();
+                    break;
+                }
+            }
+        }
+        let departure = departure.saturating_sub(walking);
+        Journey {
+            departure: TimeOffset::from_seconds(departure),
+            arrival: TimeOffset::from_seconds(arrival),
+            legs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    /// T1 runs A-B-C, T2 B-D and the slower T3 A-D
+    fn routing_feed(extra: Vec<(&'static str, &'static str)>) -> TransitFeed {
+        let mut files = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+                 A,Alpha,1.0,1.0,,\n\
+                 B,Beta,1.0,1.1,,\n\
+                 C,Gamma,1.0,1.2,,\n\
+                 DS,Delta,1.0,1.3,1,\n\
+                 D,Delta platform,1.0,1.3,0,DS\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\n\
+                 R1,1,One,3\n\
+                 R2,2,Two,3\n\
+                 R3,3,Three,3\n",
+            ),
+            (
+                "trips.txt",
+                "route_id,service_id,trip_id\nR1,S,T1\nR2,S,T2\nR3,S,T3\nR3,W,T4\n",
+            ),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence,pickup_type\n\
+                 T1,08:00:00,08:00:00,A,1,\n\
+                 T1,08:10:00,08:10:00,B,2,\n\
+                 T1,08:20:00,08:20:00,C,3,\n\
+                 T2,08:15:00,08:15:00,B,1,\n\
+                 T2,08:30:00,08:30:00,D,2,\n\
+                 T3,08:05:00,08:05:00,A,1,\n\
+                 T3,09:00:00,09:00:00,D,2,\n\
+                 T4,08:01:00,08:01:00,A,1,\n\
+                 T4,08:02:00,08:02:00,D,2,\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n\
+                 W,0,0,0,0,0,1,1,20260101,20261231\n",
+            ),
+        ];
+        for (name, contents) in extra {
+            files.retain(|&(existing, _)| existing != name);
+            files.push((name, contents));
+        }
+        let provider: MemoryFeedProvider = files.into_iter().collect();
+        TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap()
+    }
+
+    fn rides(journey: &Journey) -> Vec<&str> {
+        journey
+            .legs
+            .iter()
+            .filter_map(|leg| match *leg {
+                JourneyLeg::Ride { ref trip_id, .. } => Some(trip_id.as_str()),
+                JourneyLeg::Walk { .. } => None,
+            })
+            .collect()
+    }
+
+    fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+    }
+
+    #[test]
+    fn test_pareto_journeys() {
+        let feed = routing_feed(vec![]);
+        let router = Router::new(&feed);
+        let journeys = router.journeys("A", "DS", monday(), TimeOffset::from_hms(7, 55, 0));
+        assert_eq!(2, journeys.len());
+        assert_eq!(vec!["T3"], rides(&journeys[0]));
+        assert_eq!(0, journeys[0].transfers());
+        assert_eq!(TimeOffset::from_hms(9, 0, 0), journeys[0].arrival);
+        assert_eq!(vec!["T1", "T2"], rides(&journeys[1]));
+        assert_eq!(1, journeys[1].transfers());
+        assert_eq!(TimeOffset::from_hms(8, 0, 0), journeys[1].departure);
+        assert_eq!(TimeOffset::from_hms(8, 30, 0), journeys[1].arrival);
+
+        // T1 has left by 08:01 and the weekend service doesn't run on Monday
+        let journeys = router.journeys("A", "D", monday(), TimeOffset::from_hms(8, 1, 0));
+        assert_eq!(1, journeys.len());
+        assert_eq!(vec!["T3"], rides(&journeys[0]));
+    }
+
+    #[test]
+    fn test_transfer_rules() {
+        let feed = routing_feed(vec![(
+            "transfers.txt",
+            "from_stop_id,to_stop_id,transfer_type,min_transfer_time\nB,B,2,600\n",
+        )]);
+        let journeys =
+            Router::new(&feed).journeys("A", "D", monday(), TimeOffset::from_hms(8, 0, 0));
+        assert_eq!(
+            vec![vec!["T3"]],
+            journeys.iter().map(rides).collect::<Vec<_>>()
+        );
+
+        let feed = routing_feed(vec![(
+            "transfers.txt",
+            "from_stop_id,to_stop_id,transfer_type,min_transfer_time\nB,B,3,\n",
+        )]);
+        let journeys =
+            Router::new(&feed).journeys("A", "D", monday(), TimeOffset::from_hms(8, 0, 0));
+        assert_eq!(
+            vec![vec!["T3"]],
+            journeys.iter().map(rides).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_walking_transfer() {
+        let feed = routing_feed(vec![(
+            "transfers.txt",
+            "from_stop_id,to_stop_id,transfer_type,min_transfer_time\nC,D,2,300\n",
+        )]);
+        let journeys =
+            Router::new(&feed).journeys("A", "D", monday(), TimeOffset::from_hms(8, 0, 0));
+        assert_eq!(1, journeys.len());
+        assert_eq!(TimeOffset::from_hms(8, 25, 0), journeys[0].arrival);
+        assert_eq!(
+            JourneyLeg::Walk {
+                from_stop_id: "C".to_string(),
+                to_stop_id: "D".to_string(),
+                duration: Duration::minutes(5),
+            },
+            journeys[0].legs[1]
+        );
+    }
+
+    #[test]
+    fn test_frequency_trip() {
+        // T3 leaves A every 20 minutes from 08:00, taking 55 minutes to reach D
+        let feed = routing_feed(vec![(
+            "frequencies.txt",
+            "trip_id,start_time,end_time,headway_secs,exact_times\n\
+             T3,08:00:00,10:00:00,1200,1\n",
+        )]);
+        let journeys =
+            Router::new(&feed).journeys("A", "D", monday(), TimeOffset::from_hms(8, 10, 0));
+        assert_eq!(1, journeys.len());
+        assert_eq!(vec!["T3"], rides(&journeys[0]));
+        assert_eq!(TimeOffset::from_hms(8, 20, 0), journeys[0].departure);
+        assert_eq!(TimeOffset::from_hms(9, 15, 0), journeys[0].arrival);
+    }
+
+    #[test]
+    fn test_no_pickup() {
+        let feed = routing_feed(vec![(
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence,pickup_type\n\
+             T1,08:00:00,08:00:00,A,1,\n\
+             T1,08:10:00,08:10:00,B,2,\n\
+             T2,08:15:00,08:15:00,B,1,1\n\
+             T2,08:30:00,08:30:00,D,2,\n",
+        )]);
+        let router = Router::new(&feed);
+        assert!(router
+            .journeys("A", "D", monday(), TimeOffset::from_hms(8, 0, 0))
+            .is_empty());
+        assert!(router
+            .journeys("A", "MISSING", monday(), TimeOffset::from_hms(8, 0, 0))
+            .is_empty());
+    }
+}
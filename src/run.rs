@@ -1,7 +1,10 @@
+use chrono::Duration;
 use std::collections::HashMap;
 use std::vec::IntoIter;
 use std::iter::Iterator;
-use transit::StopTime;
+use feed::TransitFeed;
+use geo::haversine_distance;
+use transit::{Frequency, FrequencyAccuracy, StopServiceType, StopTime, TimeOffset, Timepoint};
 
 #[derive(Debug, PartialEq)]
 pub struct Run<'a> {
@@ -11,6 +14,218 @@ pub struct Run<'a> {
     pub sequence: Vec<&'a StopTime>,
 }
 
+impl<'a> Run<'a> {
+    /// Expands a frequency-based trip into a concrete run for every departure
+    ///
+    /// `self` is the template trip that `frequency` belongs to. Departures from the
+    /// first stop are made every `headway_secs` from `start_time` until `end_time`,
+    /// and every `StopTime` is shifted so the template keeps its own running times.
+    pub fn expand(&self, frequency: &Frequency) -> Vec<FrequencyRun> {
+        let first = match self
+            .sequence
+            .first()
+            .and_then(|first| first.departure_time.or(first.arrival_time))
+        {
+            Some(first) => first,
+            None => return vec![],
+        };
+        let headway = Duration::seconds(frequency.headway_secs as i64);
+        if headway <= Duration::zero() {
+            return vec![];
+        }
+
+        let mut runs = vec![];
+        let mut start = frequency.start_time.normalize();
+        while start < frequency.end_time.normalize() {
+            let offset = start - first;
+            runs.push(FrequencyRun {
+                trip: self.trip.clone(),
+                start_time: start,
+                exact_times: frequency.exact_times,
+                sequence: self
+                    .sequence
+                    .iter()
+                    .map(|stop_time| StopTime {
+                        arrival_time: stop_time.arrival_time.map(|time| time + offset),
+                        departure_time: stop_time.departure_time.map(|time| time + offset),
+                        ..(*stop_time).clone()
+                    })
+                    .collect(),
+            });
+            start += headway;
+        }
+        runs
+    }
+
+    /// Departures of the run on a service day, each with its start time and `StopTime`s
+    ///
+    /// `stop_times` are the run's own, usually from `interpolate`. A trip with rows in
+    /// frequencies.txt departs at every headway, see `expand`, while any other trip
+    /// departs once at its scheduled times and has no start time.
+    pub fn departures(
+        &self,
+        feed: &TransitFeed,
+        stop_times: Vec<StopTime>,
+    ) -> Vec<(Option<TimeOffset>, Vec<StopTime>)> {
+        let frequencies = feed.trip_frequencies(&self.trip);
+        if frequencies.is_empty() {
+            return vec![(None, stop_times)];
+        }
+        let template = Run {
+            trip: self.trip.clone(),
+            sequence: stop_times.iter().collect(),
+        };
+        frequencies
+            .iter()
+            .flat_map(|frequency| template.expand(frequency))
+            .map(|run| (Some(run.start_time), run.sequence))
+            .collect()
+    }
+
+    /// Copies the run's `StopTime`s, filling in times left blank between timepoints
+    ///
+    /// Times are spread linearly by `shape_dist_traveled` if every stop has one,
+    /// otherwise by the straight-line distance between stops in `feed`. Stops that
+    /// were filled in are marked `Timepoint::Approximate`. Returns `None` if the first
+    /// or last stop has no time since there is nothing to interpolate from.
+    pub fn interpolate(&self, feed: &TransitFeed) -> Option<Vec<StopTime>> {
+        let times: Vec<Option<(TimeOffset, TimeOffset)>> = self
+            .sequence
+            .iter()
+            .map(
+                |stop_time| match (stop_time.arrival_time, stop_time.departure_time) {
+                    (Some(arrival), Some(departure)) => Some((arrival, departure)),
+                    (Some(time), None) | (None, Some(time)) => Some((time, time)),
+                    (None, None) => None,
+                },
+            )
+            .collect();
+        if times.first()?.is_none() || times.last()?.is_none() {
+            return None;
+        }
+
+        let distances = self.distances(feed);
+        let mut stop_times: Vec<StopTime> = self.sequence.iter().map(|&st| st.clone()).collect();
+        let mut previous = 0;
+        for (index, time) in times.iter().enumerate() {
+            let (arrival, departure) = match *time {
+                Some(time) => time,
+                None => continue,
+            };
+            stop_times[index].arrival_time = Some(arrival);
+            stop_times[index].departure_time = Some(departure);
+
+            let (start, span) = match times[previous] {
+                Some((_, start)) => (start, (arrival - start).num_seconds() as f64),
+                None => continue,
+            };
+            for gap in previous + 1..index {
+                let covered = distances[index] - distances[previous];
+                let fraction = if covered > 0.0 {
+                    (distances[gap] - distances[previous]) / covered
+                } else {
+                    (gap - previous) as f64 / (index - previous) as f64
+                };
+                let time =
+                    start + Duration::seconds((span * fraction.clamp(0.0, 1.0)).round() as i64);
+                stop_times[gap].arrival_time = Some(time);
+                stop_times[gap].departure_time = Some(time);
+                stop_times[gap].timepoint = Timepoint::Approximate;
+            }
+            previous = index;
+        }
+        Some(stop_times)
+    }
+
+    /// Stretches between consecutive stops where riders can flag the vehicle down
+    ///
+    /// A stop's `continuous_pickup` covers the way to the next stop and falls back to
+    /// the route's when blank. Stretches without continuous pickup are left out.
+    pub fn flag_stop_segments(&self, feed: &TransitFeed) -> Vec<FlagStopSegment<'a>> {
+        let route_pickup = feed
+            .find_trip(&self.trip)
+            .and_then(|trip| feed.find_route(&trip.route_id))
+            .and_then(|route| route.continuous_pickup.clone());
+        self.sequence
+            .windows(2)
+            .filter_map(|pair| {
+                let pickup = pair[0]
+                    .continuous_pickup
+                    .clone()
+                    .or_else(|| route_pickup.clone())?;
+                if pickup == StopServiceType::NoServiceAvailable {
+                    return None;
+                }
+                Some(FlagStopSegment {
+                    from: pair[0],
+                    to: pair[1],
+                    pickup,
+                })
+            })
+            .collect()
+    }
+
+    /// Distance travelled to reach each stop in the sequence
+    fn distances(&self, feed: &TransitFeed) -> Vec<f64> {
+        let shape_distances: Option<Vec<f64>> = self
+            .sequence
+            .iter()
+            .map(|stop_time| stop_time.shape_dist_traveled)
+            .collect();
+        if let Some(distances) = shape_distances {
+            return distances;
+        }
+
+        let mut travelled = 0.0;
+        let mut previous: Option<(f64, f64)> = None;
+        self.sequence
+            .iter()
+            .map(|stop_time| {
+                if let Some((lat, lon)) = feed
+                    .find_stop(&stop_time.stop_id)
+                    .and_then(|stop| stop.position())
+                {
+                    if let Some((last_lat, last_lon)) = previous {
+                        travelled += haversine_distance(last_lat, last_lon, lat, lon);
+                    }
+                    previous = Some((lat, lon));
+                }
+                travelled
+            })
+            .collect()
+    }
+}
+
+/// Stretch of a run between two stops with continuous pickup, see `Run::flag_stop_segments`
+#[derive(Debug, PartialEq)]
+pub struct FlagStopSegment<'a> {
+    pub from: &'a StopTime,
+    pub to: &'a StopTime,
+    /// How riders arrange to be picked up along the way
+    pub pickup: StopServiceType,
+}
+
+/// One departure of a frequency-based trip, see `Run::expand`
+#[derive(Debug, PartialEq)]
+pub struct FrequencyRun {
+    pub trip: String,
+    /// Departure time from the first stop
+    pub start_time: TimeOffset,
+    /// Whether riders can rely on the times or only on the headway
+    pub exact_times: FrequencyAccuracy,
+    pub sequence: Vec<StopTime>,
+}
+
+impl FrequencyRun {
+    /// Borrows this departure as a `Run` so it can be handled like a scheduled trip
+    pub fn as_run(&self) -> Run<'_> {
+        Run {
+            trip: self.trip.clone(),
+            sequence: self.sequence.iter().collect(),
+        }
+    }
+}
+
 pub struct RunIterator<'a> {
     runs: IntoIter<Run<'a>>,
 }
@@ -57,7 +272,56 @@ impl<'a> Iterator for RunIterator<'a> {
 #[cfg(test)]
 mod test {
     use super::*;
-    use transit::{StopServiceType, TimeOffset, Timepoint};
+
+    #[test]
+    fn test_expand_frequency() {
+        let times = [
+            stop_time("A", 1, Some([6, 0, 0]), Some([6, 1, 0])),
+            stop_time("A", 2, Some([6, 10, 0]), Some([6, 10, 30])),
+        ];
+        let run = RunIterator::new(times.iter()).next().unwrap();
+        let frequency = Frequency {
+            trip_id: String::from("A"),
+            start_time: TimeOffset::from_hms(23, 0, 0),
+            end_time: TimeOffset::from_hms(24, 30, 0),
+            headway_secs: 1800,
+            exact_times: FrequencyAccuracy::Exact,
+        };
+
+        let runs = run.expand(&frequency);
+        let starts = runs.iter().map(|run| run.start_time).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                TimeOffset::from_hms(23, 0, 0),
+                TimeOffset::from_hms(23, 30, 0),
+                TimeOffset::from_hms(24, 0, 0),
+            ],
+            starts
+        );
+
+        let last = &runs[2];
+        assert_eq!(FrequencyAccuracy::Exact, last.exact_times);
+        assert_eq!(
+            Some(TimeOffset::from_hms(23, 59, 0)),
+            last.sequence[0].arrival_time
+        );
+        assert_eq!(
+            Some(TimeOffset::from_hms(24, 0, 0)),
+            last.sequence[0].departure_time
+        );
+        assert_eq!(
+            Some(TimeOffset::from_hms(24, 9, 0)),
+            last.sequence[1].arrival_time
+        );
+        assert_eq!(
+            Some(TimeOffset::from_hms(24, 9, 30)),
+            last.sequence[1].departure_time
+        );
+
+        let as_run = last.as_run();
+        assert_eq!("A", as_run.trip);
+        assert_eq!(vec![&last.sequence[0], &last.sequence[1]], as_run.sequence);
+    }
 
     #[test]
     fn test_form_runs_from_unsorted_sequences() {
@@ -94,14 +358,8 @@ mod test {
     ) -> StopTime {
         return StopTime {
             trip_id: String::from(trip),
-            departure_time: match departure {
-                None => TimeOffset::from_hms(0, 0, 0),
-                Some(hms) => TimeOffset::from_hms(hms[0], hms[1], hms[2]),
-            },
-            arrival_time: match arrival {
-                None => TimeOffset::from_hms(0, 0, 0),
-                Some(hms) => TimeOffset::from_hms(hms[0], hms[1], hms[2]),
-            },
+            departure_time: departure.map(|hms| TimeOffset::from_hms(hms[0], hms[1], hms[2])),
+            arrival_time: arrival.map(|hms| TimeOffset::from_hms(hms[0], hms[1], hms[2])),
             stop_id: format!("{}.{}", trip, sequence),
             stop_sequence: sequence,
             stop_headsign: None,
@@ -109,6 +367,14 @@ mod test {
             dropoff_type: StopServiceType::RegularlyScheduled,
             timepoint: Timepoint::Exact,
             shape_dist_traveled: None,
+            location_group_id: None,
+            location_id: None,
+            start_pickup_drop_off_window: None,
+            end_pickup_drop_off_window: None,
+            pickup_booking_rule_id: None,
+            drop_off_booking_rule_id: None,
+            continuous_pickup: None,
+            continuous_drop_off: None,
         };
     }
 }
@@ -0,0 +1,283 @@
+use rstar::primitives::GeomWithData;
+use rstar::{RTree, AABB};
+
+use feed::TransitFeed;
+use geo::{bounding_box, haversine_distance};
+use transit::{LocationType, Stop, WheelchairAccessible};
+
+/// Half the circumference of the earth, the furthest two stops can be apart
+const MAX_DISTANCE: f64 = 20_015_115.0;
+
+/// Radius the search for the nearest stops starts with, in metres
+const NEAREST_RADIUS: f64 = 500.0;
+
+/// Restricts the stops returned by a `SpatialIndex` query, the default allows all
+#[derive(Debug, Default)]
+pub struct StopFilter {
+    pub location_type: Option<LocationType>,
+    pub wheelchair_boarding: Option<WheelchairAccessible>,
+}
+
+impl StopFilter {
+    pub fn matches(&self, stop: &Stop) -> bool {
+        self.location_type
+            .is_none_or(|location_type| stop.location_type == location_type)
+            && self
+                .wheelchair_boarding
+                .as_ref()
+                .is_none_or(|wheelchair| stop.wheelchair_boarding == *wheelchair)
+    }
+}
+
+/// A stop's `[lon, lat]` and its position in `TransitFeed::stops`
+type Entry = GeomWithData<[f64; 2], usize>;
+
+/// R-tree of the stops of a feed for radius, nearest and bounding box queries
+///
+/// Stops without a position are left out. Distances are great-circle distances in
+/// metres. Boxes are given in degrees and may cross the antimeridian.
+pub struct SpatialIndex<'a> {
+    feed: &'a TransitFeed,
+    tree: RTree<Entry>,
+}
+
+impl<'a> SpatialIndex<'a> {
+    pub fn new(feed: &'a TransitFeed) -> SpatialIndex<'a> {
+        let entries = feed
+            .stops
+            .iter()
+            .enumerate()
+            .filter_map(|(index, stop)| {
+                let (lat, lon) = stop.position()?;
+                Some(Entry::new([lon, lat], index))
+            })
+            .collect();
+        SpatialIndex {
+            feed,
+            tree: RTree::bulk_load(entries),
+        }
+    }
+
+    /// Stops within `radius` metres of `(lat, lon)` with their distance, nearest first
+    pub fn within(
+        &self,
+        lat: f64,
+        lon: f64,
+        radius: f64,
+        filter: &StopFilter,
+    ) -> Vec<(&'a Stop, f64)> {
+        let (min_lat, min_lon, max_lat, max_lon) = bounding_box(lat, lon, radius);
+        let mut stops: Vec<(&'a Stop, f64)> = self
+            .locate(min_lat, wrap(min_lon), max_lat, wrap(max_lon), filter)
+            .into_iter()
+            .filter_map(|stop| {
+                let (stop_lat, stop_lon) = stop.position()?;
+                Some((stop, haversine_distance(lat, lon, stop_lat, stop_lon)))
+            })
+            .filter(|&(_, distance)| distance <= radius)
+            .collect();
+        stops.sort_by(|a, b| a.1.total_cmp(&b.1));
+        stops
+    }
+
+    /// The `k` stops nearest to `(lat, lon)` with their distance, nearest first
+    ///
+    /// Fewer are returned if the feed doesn't have `k` stops matching `filter`.
+    pub fn nearest(
+        &self,
+        lat: f64,
+        lon: f64,
+        k: usize,
+        filter: &StopFilter,
+    ) -> Vec<(&'a Stop, f64)> {
+        if k == 0 {
+            return vec![];
+        }
+        // the k nearest of the stops within a radius are the k nearest of all
+        let mut radius = NEAREST_RADIUS;
+        loop {
+            let mut stops = self.within(lat, lon, radius, filter);
+            if stops.len() >= k || radius >= MAX_DISTANCE {
+                stops.truncate(k);
+                return stops;
+            }
+            radius *= 4.0;
+        }
+    }
+
+    /// Stops inside a box, in the order of `TransitFeed::stops`
+    ///
+    /// The box crosses the antimeridian if `min_lon` is greater than `max_lon`.
+    pub fn in_bbox(
+        &self,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+        filter: &StopFilter,
+    ) -> Vec<&'a Stop> {
+        self.locate(min_lat, min_lon, max_lat, max_lon, filter)
+    }
+
+    fn locate(
+        &self,
+        min_lat: f64,
+        min_lon: f64,
+        max_lat: f64,
+        max_lon: f64,
+        filter: &StopFilter,
+    ) -> Vec<&'a Stop> {
+        let envelopes = if min_lon > max_lon {
+            vec![(min_lon, 180.0), (-180.0, max_lon)]
+        } else {
+            vec![(min_lon, max_lon)]
+        };
+        let mut indices: Vec<usize> = envelopes
+            .into_iter()
+            .flat_map(|(min_lon, max_lon)| {
+                let envelope = AABB::from_corners([min_lon, min_lat], [max_lon, max_lat]);
+                self.tree
+                    .locate_in_envelope(&envelope)
+                    .map(|entry| entry.data)
+            })
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| &self.feed.stops[index])
+            .filter(|stop| filter.matches(stop))
+            .collect()
+    }
+}
+
+/// A longitude brought back into -180..=180 degrees
+fn wrap(lon: f64) -> f64 {
+    if lon < -180.0 {
+        lon + 360.0
+    } else if lon > 180.0 {
+        lon - 360.0
+    } else {
+        lon
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    fn stop_feed() -> TransitFeed {
+        // A, B and C are about 100m apart in a row, F and W are across the antimeridian
+        let provider: MemoryFeedProvider = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station,\
+                 wheelchair_boarding\n\
+                 S,Station,1.0,1.0,1,,\n\
+                 A,Alpha,1.0,1.0,0,S,1\n\
+                 B,Beta,1.0009,1.0,0,,2\n\
+                 C,Gamma,1.0018,1.0,0,,1\n\
+                 D,Delta,2.0,1.0,0,,\n\
+                 F,Fiji,-17.0,179.9995,0,,\n\
+                 W,Wallis,-17.0,-179.9995,0,,\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\nR,1,One,3\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR,S,T\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,A,1\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ]
+        .into_iter()
+        .collect();
+        TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap()
+    }
+
+    fn ids<'a, I: IntoIterator<Item = &'a Stop>>(stops: I) -> Vec<&'a str> {
+        stops
+            .into_iter()
+            .map(|stop| stop.stop_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_within() {
+        let feed = stop_feed();
+        let index = SpatialIndex::new(&feed);
+        let all = StopFilter::default();
+        let stops = index.within(1.0, 1.0, 150.0, &all);
+        assert_eq!(
+            vec!["S", "A", "B"],
+            ids(stops.iter().map(|&(stop, _)| stop))
+        );
+        assert!((stops[2].1 - 100.0).abs() < 1.0, "{}", stops[2].1);
+
+        let platforms = StopFilter {
+            location_type: Some(LocationType::Stop),
+            ..StopFilter::default()
+        };
+        let stops = index.within(1.0, 1.0, 250.0, &platforms);
+        assert_eq!(
+            vec!["A", "B", "C"],
+            ids(stops.iter().map(|&(stop, _)| stop))
+        );
+
+        // F and W are about 100m apart across the antimeridian
+        let stops = index.within(-17.0, 179.9995, 150.0, &all);
+        assert_eq!(vec!["F", "W"], ids(stops.iter().map(|&(stop, _)| stop)));
+    }
+
+    #[test]
+    fn test_nearest() {
+        let feed = stop_feed();
+        let index = SpatialIndex::new(&feed);
+        let accessible = StopFilter {
+            wheelchair_boarding: Some(WheelchairAccessible::SomeAccessibility),
+            ..StopFilter::default()
+        };
+        let stops = index.nearest(1.0009, 1.0, 2, &accessible);
+        assert_eq!(vec!["A", "C"], ids(stops.iter().map(|&(stop, _)| stop)));
+
+        // D is over 100km away
+        let stops = index.nearest(1.0, 1.0, 5, &StopFilter::default());
+        assert_eq!(
+            vec!["S", "A", "B", "C", "D"],
+            ids(stops.iter().map(|&(stop, _)| stop))
+        );
+        assert_eq!(7, index.nearest(0.0, 0.0, 10, &StopFilter::default()).len());
+        assert!(index
+            .nearest(1.0, 1.0, 0, &StopFilter::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_in_bbox() {
+        let feed = stop_feed();
+        let index = SpatialIndex::new(&feed);
+        let all = StopFilter::default();
+        assert_eq!(
+            vec!["S", "A", "B"],
+            ids(index.in_bbox(0.5, 0.5, 1.001, 1.5, &all))
+        );
+        assert_eq!(
+            vec!["F", "W"],
+            ids(index.in_bbox(-18.0, 179.0, -16.0, -179.0, &all))
+        );
+        assert!(index.in_bbox(-18.0, -179.0, -16.0, 179.0, &all).is_empty());
+    }
+}
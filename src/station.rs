@@ -0,0 +1,226 @@
+use chrono::Duration;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use feed::TransitFeed;
+use geo::{haversine_distance, WALKING_SPEED};
+use transit::{Pathway, PathwayMode};
+
+/// A pathway that can be walked in one direction
+#[derive(Debug)]
+struct Edge<'a> {
+    to: &'a str,
+    seconds: u64,
+    mode: PathwayMode,
+}
+
+/// Graph of the pathways.txt connections between stops, entrances and nodes of stations
+pub struct StationGraph<'a> {
+    feed: &'a TransitFeed,
+    edges: HashMap<&'a str, Vec<Edge<'a>>>,
+}
+
+impl<'a> StationGraph<'a> {
+    pub fn new(feed: &'a TransitFeed) -> StationGraph<'a> {
+        let mut edges: HashMap<&str, Vec<Edge>> = HashMap::new();
+        if let Some(ref pathways) = feed.pathways {
+            for pathway in pathways {
+                let seconds = traversal_seconds(feed, pathway);
+                edges
+                    .entry(pathway.from_stop_id.as_str())
+                    .or_default()
+                    .push(Edge {
+                        to: &pathway.to_stop_id,
+                        seconds,
+                        mode: pathway.pathway_mode,
+                    });
+                if pathway.is_bidirectional {
+                    edges
+                        .entry(pathway.to_stop_id.as_str())
+                        .or_default()
+                        .push(Edge {
+                            to: &pathway.from_stop_id,
+                            seconds,
+                            mode: pathway.pathway_mode,
+                        });
+                }
+            }
+        }
+        StationGraph { feed, edges }
+    }
+
+    /// Shortest time to walk from `from` to `to`, `None` if there is no way through
+    ///
+    /// A platform is left from or reached at any of its boarding areas as well.
+    /// With `step_free` set, stairs and escalators are avoided.
+    pub fn walking_time(&self, from: &str, to: &str, step_free: bool) -> Option<Duration> {
+        let mut targets: HashSet<&str> = self.with_boarding_areas(to).into_iter().collect();
+        targets.insert(to);
+
+        let mut best: HashMap<&str, u64> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for start in self.with_boarding_areas(from) {
+            best.insert(start, 0);
+            queue.push(Reverse((0, start)));
+        }
+        while let Some(Reverse((seconds, stop_id))) = queue.pop() {
+            if targets.contains(stop_id) {
+                return Some(Duration::seconds(seconds as i64));
+            }
+            if best.get(stop_id).is_some_and(|&known| known < seconds) {
+                continue;
+            }
+            for edge in self.edges.get(stop_id).into_iter().flatten() {
+                if step_free && !edge.mode.is_step_free() {
+                    continue;
+                }
+                let arrival = seconds + edge.seconds;
+                if best.get(edge.to).is_none_or(|&known| arrival < known) {
+                    best.insert(edge.to, arrival);
+                    queue.push(Reverse((arrival, edge.to)));
+                }
+            }
+        }
+        None
+    }
+
+    /// `stop_id` followed by the ids of its boarding areas
+    fn with_boarding_areas<'b>(&self, stop_id: &'b str) -> Vec<&'b str>
+    where
+        'a: 'b,
+    {
+        let mut ids = vec![stop_id];
+        ids.extend(
+            self.feed
+                .boarding_areas(stop_id)
+                .into_iter()
+                .map(|stop| stop.stop_id.as_str()),
+        );
+        ids
+    }
+}
+
+/// Seconds to walk a pathway, estimated from its length or the stops' positions if needed
+fn traversal_seconds(feed: &TransitFeed, pathway: &Pathway) -> u64 {
+    if let Some(seconds) = pathway.traversal_time {
+        return seconds;
+    }
+    let length = pathway.length.or_else(|| {
+        match (
+            feed.find_stop(&pathway.from_stop_id),
+            feed.find_stop(&pathway.to_stop_id),
+        ) {
+            (Some(from), Some(to)) => match (from.position(), to.position()) {
+                (Some((from_lat, from_lon)), Some((to_lat, to_lon))) => {
+                    Some(haversine_distance(from_lat, from_lon, to_lat, to_lon))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    });
+    (length.unwrap_or(0.0) / WALKING_SPEED).round() as u64
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    fn station_feed() -> TransitFeed {
+        let provider: MemoryFeedProvider = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+                 ST,Central,1.0,1.0,1,\n\
+                 E,Entrance,1.0,1.0,2,ST\n\
+                 N,Mezzanine,1.0,1.0,3,ST\n\
+                 P,Platform,1.0,1.0,0,ST\n\
+                 PB,Platform lift,1.0,1.0,4,P\n\
+                 Q,Other platform,1.0,1.0,0,ST\n\
+                 R,Far platform,1.0,1.0,0,ST\n\
+                 RB,Far platform lift,1.0,1.0,4,R\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\nR,1,One,1\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR,S,T\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,P,1\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+            (
+                "pathways.txt",
+                "pathway_id,from_stop_id,to_stop_id,pathway_mode,is_bidirectional,length,\
+                 traversal_time\n\
+                 W1,E,N,1,1,,60\n\
+                 S1,N,P,2,1,,30\n\
+                 L1,N,PB,5,1,,90\n\
+                 X1,E,P,4,0,,20\n\
+                 W2,P,Q,1,1,13,\n\
+                 L2,N,RB,5,1,,45\n",
+            ),
+        ]
+        .into_iter()
+        .collect();
+        TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap()
+    }
+
+    #[test]
+    fn test_walking_time() {
+        let feed = station_feed();
+        let graph = StationGraph::new(&feed);
+        assert_eq!(
+            Some(Duration::seconds(20)),
+            graph.walking_time("E", "P", false)
+        );
+        // the escalator is one way so the way out is by the stairs
+        assert_eq!(
+            Some(Duration::seconds(90)),
+            graph.walking_time("P", "E", false)
+        );
+        // length is walked at WALKING_SPEED
+        assert_eq!(
+            Some(Duration::seconds(30)),
+            graph.walking_time("E", "Q", false)
+        );
+        assert_eq!(None, graph.walking_time("E", "MISSING", false));
+    }
+
+    #[test]
+    fn test_step_free_walking_time() {
+        let feed = station_feed();
+        let graph = StationGraph::new(&feed);
+        // the lift reaches the platform's boarding area
+        assert_eq!(
+            Some(Duration::seconds(150)),
+            graph.walking_time("E", "P", true)
+        );
+        assert_eq!(
+            Some(Duration::seconds(150)),
+            graph.walking_time("PB", "E", true)
+        );
+        assert_eq!(None, graph.walking_time("Q", "E", true));
+        // the far platform only has pathways from its boarding area
+        assert_eq!(
+            Some(Duration::seconds(105)),
+            graph.walking_time("R", "E", true)
+        );
+        assert_eq!(
+            Some(Duration::seconds(105)),
+            graph.walking_time("E", "R", true)
+        );
+    }
+}
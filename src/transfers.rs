@@ -0,0 +1,158 @@
+use chrono::Duration;
+use std::collections::HashSet;
+
+use feed::TransitFeed;
+use geo::nearby_pairs;
+use transit::{LocationType, Stop, Transfer, TransferType};
+
+/// `MinimumTime` transfers both ways between stops within `max_distance` metres
+///
+/// The `min_transfer_time` is the great-circle distance walked at `walking_speed`
+/// metres per second, rounded up to the second. Stops that already have a transfer
+/// in transfers.txt are left as they are, and no transfer is made where a
+/// `NotPossible` transfer forbids it, whether between the stops or their stations.
+/// Transfers are ordered by from_stop_id then to_stop_id, ready to be appended to
+/// `TransitFeed::transfers` or written out with `FeedWriter::write_transfers`.
+pub fn walking_transfers(
+    feed: &TransitFeed,
+    max_distance: f64,
+    walking_speed: f64,
+) -> Vec<Transfer> {
+    let mut transfers: Vec<Transfer> = nearby_walks(feed, max_distance, walking_speed)
+        .into_iter()
+        .map(|(from, to, seconds)| Transfer {
+            from_stop_id: from.stop_id.clone(),
+            to_stop_id: to.stop_id.clone(),
+            transfer_type: TransferType::MinimumTime,
+            min_transfer_time: Some(Duration::seconds(i64::from(seconds))),
+        })
+        .collect();
+    transfers
+        .sort_by(|a, b| (&a.from_stop_id, &a.to_stop_id).cmp(&(&b.from_stop_id, &b.to_stop_id)));
+    transfers
+}
+
+/// Walks both ways between stops within `max_distance` metres and the seconds they take
+///
+/// The seconds are the great-circle distance walked at `walking_speed` metres per second,
+/// rounded up. Stops that already have a transfer in transfers.txt are left out, as is
+/// any walk that a `NotPossible` transfer forbids, whether between the stops or their
+/// stations. Only stops with a position are considered.
+pub fn nearby_walks(
+    feed: &TransitFeed,
+    max_distance: f64,
+    walking_speed: f64,
+) -> Vec<(&Stop, &Stop, u32)> {
+    let mut existing = HashSet::new();
+    let mut forbidden = HashSet::new();
+    for transfer in feed.transfers.iter().flatten() {
+        let pair = (transfer.from_stop_id.as_str(), transfer.to_stop_id.as_str());
+        existing.insert(pair);
+        if let TransferType::NotPossible = transfer.transfer_type {
+            forbidden.insert(pair);
+        }
+    }
+    let is_forbidden = |from: &Stop, to: &Stop| {
+        with_station(from).iter().any(|&from| {
+            with_station(to)
+                .iter()
+                .any(|&to| forbidden.contains(&(from, to)))
+        })
+    };
+
+    let stops: Vec<&Stop> = feed
+        .stops
+        .iter()
+        .filter(|stop| stop.location_type == LocationType::Stop && stop.position().is_some())
+        .collect();
+    let points: Vec<(f64, f64)> = stops.iter().filter_map(|stop| stop.position()).collect();
+    let mut walks = vec![];
+    for (a, b, distance) in nearby_pairs(&points, max_distance) {
+        let seconds = (distance / walking_speed).ceil() as u32;
+        for &(from, to) in &[(stops[a], stops[b]), (stops[b], stops[a])] {
+            if from.stop_id == to.stop_id
+                || existing.contains(&(from.stop_id.as_str(), to.stop_id.as_str()))
+                || is_forbidden(from, to)
+            {
+                continue;
+            }
+            walks.push((from, to, seconds));
+        }
+    }
+    walks
+}
+
+/// The id of `stop` followed by its parent station's if it has one
+fn with_station(stop: &Stop) -> Vec<&str> {
+    let mut ids = vec![stop.stop_id.as_str()];
+    ids.extend(stop.parent_station.as_deref());
+    ids
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+    use geo::WALKING_SPEED;
+
+    #[test]
+    fn test_walking_transfers() {
+        // A, B and C are about 100m apart in a row, D is far away and S is a station
+        let provider: MemoryFeedProvider = vec![
+            (
+                "agency.txt",
+                "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+                 S,Station,1.0,1.0,1,\n\
+                 A,Alpha,1.0,1.0,0,S\n\
+                 B,Beta,1.0009,1.0,0,\n\
+                 C,Gamma,1.0018,1.0,0,\n\
+                 D,Delta,2.0,1.0,0,\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,route_short_name,route_long_name,route_type\nR,1,One,3\n",
+            ),
+            ("trips.txt", "route_id,service_id,trip_id\nR,S,T\n"),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,A,1\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 S,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+            (
+                "transfers.txt",
+                "from_stop_id,to_stop_id,transfer_type,min_transfer_time\n\
+                 S,B,3,\n\
+                 C,B,2,300\n",
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+
+        let transfers = walking_transfers(&feed, 150.0, WALKING_SPEED);
+        let transfers: Vec<(&str, &str, Option<i64>)> = transfers
+            .iter()
+            .map(|transfer| {
+                assert!(matches!(transfer.transfer_type, TransferType::MinimumTime));
+                (
+                    transfer.from_stop_id.as_str(),
+                    transfer.to_stop_id.as_str(),
+                    transfer.min_transfer_time.map(|time| time.num_seconds()),
+                )
+            })
+            .collect();
+        // A to B crosses the forbidden transfer from its station and C to B exists
+        assert_eq!(vec![("B", "A", Some(77)), ("B", "C", Some(77))], transfers);
+        assert_eq!(4, walking_transfers(&feed, 250.0, WALKING_SPEED).len());
+    }
+}
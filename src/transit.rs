@@ -1,9 +1,13 @@
-use chrono::{Duration, NaiveDate};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone};
 use gtfs::parse::*;
 use serde;
+use std::error::Error as StdError;
+use std::fmt;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
 
 /// Agency
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Agency {
     pub agency_id: Option<String>,
     pub agency_name: String,
@@ -16,10 +20,17 @@ pub struct Agency {
 }
 
 /// Location Type
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum LocationType {
+    /// Stop or platform
     Stop,
     Station,
+    /// Entrance or exit of a station
+    Entrance,
+    /// Location within a station used to link pathways
+    GenericNode,
+    /// Specific location on a platform where riders board
+    BoardingArea,
 }
 
 impl Default for LocationType {
@@ -39,23 +50,41 @@ impl<'de> serde::Deserialize<'de> for LocationType {
             r => match r.parse::<u32>() {
                 Ok(0) => Ok(LocationType::Stop),
                 Ok(1) => Ok(LocationType::Station),
-                _ => Err(serde::de::Error::custom(
-                    "Location type must (currently) be 0 or 1",
-                )),
+                Ok(2) => Ok(LocationType::Entrance),
+                Ok(3) => Ok(LocationType::GenericNode),
+                Ok(4) => Ok(LocationType::BoardingArea),
+                _ => Err(serde::de::Error::custom("Location type must be 0-4")),
             },
         }
     }
 }
 
+impl serde::Serialize for LocationType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            LocationType::Stop => 0,
+            LocationType::Station => 1,
+            LocationType::Entrance => 2,
+            LocationType::GenericNode => 3,
+            LocationType::BoardingArea => 4,
+        })
+    }
+}
+
 /// Stop
-#[derive(Debug, Deserialize, PartialEq)]
+///
+/// The name and position are only optional for generic nodes and boarding areas.
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
 pub struct Stop {
     pub stop_id: String,
     pub stop_code: Option<String>,
-    pub stop_name: String,
+    pub stop_name: Option<String>,
     pub stop_desc: Option<String>,
-    pub stop_lat: f64,
-    pub stop_lon: f64,
+    pub stop_lat: Option<f64>,
+    pub stop_lon: Option<f64>,
     pub zone_id: Option<String>,
     pub stop_url: Option<String>,
     #[serde(default)]
@@ -64,19 +93,428 @@ pub struct Stop {
     pub stop_timezone: Option<String>,
     #[serde(default)]
     pub wheelchair_boarding: WheelchairAccessible,
+    pub level_id: Option<String>,
+}
+
+impl Stop {
+    /// `(lat, lon)` of the stop if it has both
+    pub fn position(&self) -> Option<(f64, f64)> {
+        match (self.stop_lat, self.stop_lon) {
+            (Some(lat), Some(lon)) => Some((lat, lon)),
+            _ => None,
+        }
+    }
 }
 
 /// RouteType
-#[derive(Debug)]
+///
+/// Covers the basic GTFS values and the extended Hierarchical Vehicle Type codes,
+/// anything else is kept as `Other`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum RouteType {
+    /// Tram, streetcar or light rail
     LightRail,
+    /// Subway or metro
     Subway,
+    /// Intercity or long-distance rail
     Rail,
     Bus,
     Ferry,
+    /// Street-level rail cable car
     CableCar,
+    /// Aerial lift or suspended cable car
     Gondola,
     Funicular,
+    Trolleybus,
+    Monorail,
+    // extended rail, 100-117
+    RailwayService,
+    HighSpeedRail,
+    LongDistanceTrains,
+    InterRegionalRail,
+    CarTransportRail,
+    SleeperRail,
+    RegionalRail,
+    TouristRailway,
+    RailShuttle,
+    SuburbanRailway,
+    ReplacementRail,
+    SpecialRail,
+    LorryTransportRail,
+    AllRailServices,
+    CrossCountryRail,
+    VehicleTransportRail,
+    RackAndPinionRailway,
+    AdditionalRail,
+    // extended coach, 200-209
+    CoachService,
+    InternationalCoach,
+    NationalCoach,
+    ShuttleCoach,
+    RegionalCoach,
+    SpecialCoach,
+    SightseeingCoach,
+    TouristCoach,
+    CommuterCoach,
+    AllCoachServices,
+    // extended urban rail, 400-405
+    UrbanRailwayService,
+    Metro,
+    Underground,
+    UrbanRailway,
+    AllUrbanRailwayServices,
+    UrbanMonorail,
+    // extended bus, 700-716
+    BusService,
+    RegionalBus,
+    ExpressBus,
+    StoppingBus,
+    LocalBus,
+    NightBus,
+    PostBus,
+    SpecialNeedsBus,
+    MobilityBus,
+    MobilityBusForRegisteredDisabled,
+    SightseeingBus,
+    ShuttleBus,
+    SchoolBus,
+    SchoolAndPublicServiceBus,
+    RailReplacementBus,
+    DemandAndResponseBus,
+    AllBusServices,
+    // extended trolleybus, 800
+    TrolleybusService,
+    // extended tram, 900-906
+    TramService,
+    CityTram,
+    LocalTram,
+    RegionalTram,
+    SightseeingTram,
+    ShuttleTram,
+    AllTramServices,
+    // extended water transport, 1000
+    WaterTransportService,
+    // extended air, 1100
+    AirService,
+    // extended ferry, 1200
+    FerryService,
+    // extended aerial lift, 1300-1307
+    AerialLiftService,
+    Telecabin,
+    AerialCableCar,
+    Elevator,
+    ChairLift,
+    DragLift,
+    SmallTelecabin,
+    AllTelecabinServices,
+    // extended funicular, 1400
+    FunicularService,
+    // extended taxi, 1500-1507
+    TaxiService,
+    CommunalTaxi,
+    WaterTaxi,
+    RailTaxi,
+    BikeTaxi,
+    LicensedTaxi,
+    PrivateHireVehicle,
+    AllTaxiServices,
+    // extended miscellaneous, 1700-1702
+    MiscellaneousService,
+    /// Street-running cable car, as opposed to an aerial `AerialCableCar`
+    CableCarService,
+    HorseDrawnCarriage,
+    Other(u32),
+}
+
+impl RouteType {
+    /// Route type for a `route_type` value, unknown values become `Other`
+    pub fn from_code(code: u32) -> RouteType {
+        match code {
+            0 => RouteType::LightRail,
+            1 => RouteType::Subway,
+            2 => RouteType::Rail,
+            3 => RouteType::Bus,
+            4 => RouteType::Ferry,
+            5 => RouteType::CableCar,
+            6 => RouteType::Gondola,
+            7 => RouteType::Funicular,
+            11 => RouteType::Trolleybus,
+            12 => RouteType::Monorail,
+            100 => RouteType::RailwayService,
+            101 => RouteType::HighSpeedRail,
+            102 => RouteType::LongDistanceTrains,
+            103 => RouteType::InterRegionalRail,
+            104 => RouteType::CarTransportRail,
+            105 => RouteType::SleeperRail,
+            106 => RouteType::RegionalRail,
+            107 => RouteType::TouristRailway,
+            108 => RouteType::RailShuttle,
+            109 => RouteType::SuburbanRailway,
+            110 => RouteType::ReplacementRail,
+            111 => RouteType::SpecialRail,
+            112 => RouteType::LorryTransportRail,
+            113 => RouteType::AllRailServices,
+            114 => RouteType::CrossCountryRail,
+            115 => RouteType::VehicleTransportRail,
+            116 => RouteType::RackAndPinionRailway,
+            117 => RouteType::AdditionalRail,
+            200 => RouteType::CoachService,
+            201 => RouteType::InternationalCoach,
+            202 => RouteType::NationalCoach,
+            203 => RouteType::ShuttleCoach,
+            204 => RouteType::RegionalCoach,
+            205 => RouteType::SpecialCoach,
+            206 => RouteType::SightseeingCoach,
+            207 => RouteType::TouristCoach,
+            208 => RouteType::CommuterCoach,
+            209 => RouteType::AllCoachServices,
+            400 => RouteType::UrbanRailwayService,
+            401 => RouteType::Metro,
+            402 => RouteType::Underground,
+            403 => RouteType::UrbanRailway,
+            404 => RouteType::AllUrbanRailwayServices,
+            405 => RouteType::UrbanMonorail,
+            700 => RouteType::BusService,
+            701 => RouteType::RegionalBus,
+            702 => RouteType::ExpressBus,
+            703 => RouteType::StoppingBus,
+            704 => RouteType::LocalBus,
+            705 => RouteType::NightBus,
+            706 => RouteType::PostBus,
+            707 => RouteType::SpecialNeedsBus,
+            708 => RouteType::MobilityBus,
+            709 => RouteType::MobilityBusForRegisteredDisabled,
+            710 => RouteType::SightseeingBus,
+            711 => RouteType::ShuttleBus,
+            712 => RouteType::SchoolBus,
+            713 => RouteType::SchoolAndPublicServiceBus,
+            714 => RouteType::RailReplacementBus,
+            715 => RouteType::DemandAndResponseBus,
+            716 => RouteType::AllBusServices,
+            800 => RouteType::TrolleybusService,
+            900 => RouteType::TramService,
+            901 => RouteType::CityTram,
+            902 => RouteType::LocalTram,
+            903 => RouteType::RegionalTram,
+            904 => RouteType::SightseeingTram,
+            905 => RouteType::ShuttleTram,
+            906 => RouteType::AllTramServices,
+            1000 => RouteType::WaterTransportService,
+            1100 => RouteType::AirService,
+            1200 => RouteType::FerryService,
+            1300 => RouteType::AerialLiftService,
+            1301 => RouteType::Telecabin,
+            1302 => RouteType::AerialCableCar,
+            1303 => RouteType::Elevator,
+            1304 => RouteType::ChairLift,
+            1305 => RouteType::DragLift,
+            1306 => RouteType::SmallTelecabin,
+            1307 => RouteType::AllTelecabinServices,
+            1400 => RouteType::FunicularService,
+            1500 => RouteType::TaxiService,
+            1501 => RouteType::CommunalTaxi,
+            1502 => RouteType::WaterTaxi,
+            1503 => RouteType::RailTaxi,
+            1504 => RouteType::BikeTaxi,
+            1505 => RouteType::LicensedTaxi,
+            1506 => RouteType::PrivateHireVehicle,
+            1507 => RouteType::AllTaxiServices,
+            1700 => RouteType::MiscellaneousService,
+            1701 => RouteType::CableCarService,
+            1702 => RouteType::HorseDrawnCarriage,
+            code => RouteType::Other(code),
+        }
+    }
+
+    /// The `route_type` value
+    pub fn code(&self) -> u32 {
+        match *self {
+            RouteType::LightRail => 0,
+            RouteType::Subway => 1,
+            RouteType::Rail => 2,
+            RouteType::Bus => 3,
+            RouteType::Ferry => 4,
+            RouteType::CableCar => 5,
+            RouteType::Gondola => 6,
+            RouteType::Funicular => 7,
+            RouteType::Trolleybus => 11,
+            RouteType::Monorail => 12,
+            RouteType::RailwayService => 100,
+            RouteType::HighSpeedRail => 101,
+            RouteType::LongDistanceTrains => 102,
+            RouteType::InterRegionalRail => 103,
+            RouteType::CarTransportRail => 104,
+            RouteType::SleeperRail => 105,
+            RouteType::RegionalRail => 106,
+            RouteType::TouristRailway => 107,
+            RouteType::RailShuttle => 108,
+            RouteType::SuburbanRailway => 109,
+            RouteType::ReplacementRail => 110,
+            RouteType::SpecialRail => 111,
+            RouteType::LorryTransportRail => 112,
+            RouteType::AllRailServices => 113,
+            RouteType::CrossCountryRail => 114,
+            RouteType::VehicleTransportRail => 115,
+            RouteType::RackAndPinionRailway => 116,
+            RouteType::AdditionalRail => 117,
+            RouteType::CoachService => 200,
+            RouteType::InternationalCoach => 201,
+            RouteType::NationalCoach => 202,
+            RouteType::ShuttleCoach => 203,
+            RouteType::RegionalCoach => 204,
+            RouteType::SpecialCoach => 205,
+            RouteType::SightseeingCoach => 206,
+            RouteType::TouristCoach => 207,
+            RouteType::CommuterCoach => 208,
+            RouteType::AllCoachServices => 209,
+            RouteType::UrbanRailwayService => 400,
+            RouteType::Metro => 401,
+            RouteType::Underground => 402,
+            RouteType::UrbanRailway => 403,
+            RouteType::AllUrbanRailwayServices => 404,
+            RouteType::UrbanMonorail => 405,
+            RouteType::BusService => 700,
+            RouteType::RegionalBus => 701,
+            RouteType::ExpressBus => 702,
+            RouteType::StoppingBus => 703,
+            RouteType::LocalBus => 704,
+            RouteType::NightBus => 705,
+            RouteType::PostBus => 706,
+            RouteType::SpecialNeedsBus => 707,
+            RouteType::MobilityBus => 708,
+            RouteType::MobilityBusForRegisteredDisabled => 709,
+            RouteType::SightseeingBus => 710,
+            RouteType::ShuttleBus => 711,
+            RouteType::SchoolBus => 712,
+            RouteType::SchoolAndPublicServiceBus => 713,
+            RouteType::RailReplacementBus => 714,
+            RouteType::DemandAndResponseBus => 715,
+            RouteType::AllBusServices => 716,
+            RouteType::TrolleybusService => 800,
+            RouteType::TramService => 900,
+            RouteType::CityTram => 901,
+            RouteType::LocalTram => 902,
+            RouteType::RegionalTram => 903,
+            RouteType::SightseeingTram => 904,
+            RouteType::ShuttleTram => 905,
+            RouteType::AllTramServices => 906,
+            RouteType::WaterTransportService => 1000,
+            RouteType::AirService => 1100,
+            RouteType::FerryService => 1200,
+            RouteType::AerialLiftService => 1300,
+            RouteType::Telecabin => 1301,
+            RouteType::AerialCableCar => 1302,
+            RouteType::Elevator => 1303,
+            RouteType::ChairLift => 1304,
+            RouteType::DragLift => 1305,
+            RouteType::SmallTelecabin => 1306,
+            RouteType::AllTelecabinServices => 1307,
+            RouteType::FunicularService => 1400,
+            RouteType::TaxiService => 1500,
+            RouteType::CommunalTaxi => 1501,
+            RouteType::WaterTaxi => 1502,
+            RouteType::RailTaxi => 1503,
+            RouteType::BikeTaxi => 1504,
+            RouteType::LicensedTaxi => 1505,
+            RouteType::PrivateHireVehicle => 1506,
+            RouteType::AllTaxiServices => 1507,
+            RouteType::MiscellaneousService => 1700,
+            RouteType::CableCarService => 1701,
+            RouteType::HorseDrawnCarriage => 1702,
+            RouteType::Other(code) => code,
+        }
+    }
+
+    /// Maps an extended route type to the basic type for its mode
+    ///
+    /// Basic types map to themselves. Elevators are aerial lifts like the rest of
+    /// their group so they map to `Gondola`. Modes without a basic type (air, taxi,
+    /// miscellaneous other than cable cars) and unknown codes return `None`.
+    pub fn basic_type(&self) -> Option<RouteType> {
+        match *self {
+            RouteType::LightRail
+            | RouteType::Subway
+            | RouteType::Rail
+            | RouteType::Bus
+            | RouteType::Ferry
+            | RouteType::CableCar
+            | RouteType::Gondola
+            | RouteType::Funicular
+            | RouteType::Trolleybus
+            | RouteType::Monorail => Some(*self),
+            RouteType::RailwayService
+            | RouteType::HighSpeedRail
+            | RouteType::LongDistanceTrains
+            | RouteType::InterRegionalRail
+            | RouteType::CarTransportRail
+            | RouteType::SleeperRail
+            | RouteType::RegionalRail
+            | RouteType::TouristRailway
+            | RouteType::RailShuttle
+            | RouteType::SuburbanRailway
+            | RouteType::ReplacementRail
+            | RouteType::SpecialRail
+            | RouteType::LorryTransportRail
+            | RouteType::AllRailServices
+            | RouteType::CrossCountryRail
+            | RouteType::VehicleTransportRail
+            | RouteType::RackAndPinionRailway
+            | RouteType::AdditionalRail => Some(RouteType::Rail),
+            RouteType::CoachService
+            | RouteType::InternationalCoach
+            | RouteType::NationalCoach
+            | RouteType::ShuttleCoach
+            | RouteType::RegionalCoach
+            | RouteType::SpecialCoach
+            | RouteType::SightseeingCoach
+            | RouteType::TouristCoach
+            | RouteType::CommuterCoach
+            | RouteType::AllCoachServices
+            | RouteType::BusService
+            | RouteType::RegionalBus
+            | RouteType::ExpressBus
+            | RouteType::StoppingBus
+            | RouteType::LocalBus
+            | RouteType::NightBus
+            | RouteType::PostBus
+            | RouteType::SpecialNeedsBus
+            | RouteType::MobilityBus
+            | RouteType::MobilityBusForRegisteredDisabled
+            | RouteType::SightseeingBus
+            | RouteType::ShuttleBus
+            | RouteType::SchoolBus
+            | RouteType::SchoolAndPublicServiceBus
+            | RouteType::RailReplacementBus
+            | RouteType::DemandAndResponseBus
+            | RouteType::AllBusServices => Some(RouteType::Bus),
+            RouteType::UrbanRailwayService
+            | RouteType::Metro
+            | RouteType::Underground
+            | RouteType::UrbanRailway
+            | RouteType::AllUrbanRailwayServices => Some(RouteType::Subway),
+            RouteType::UrbanMonorail => Some(RouteType::Monorail),
+            RouteType::TrolleybusService => Some(RouteType::Trolleybus),
+            RouteType::TramService
+            | RouteType::CityTram
+            | RouteType::LocalTram
+            | RouteType::RegionalTram
+            | RouteType::SightseeingTram
+            | RouteType::ShuttleTram
+            | RouteType::AllTramServices => Some(RouteType::LightRail),
+            RouteType::WaterTransportService | RouteType::FerryService => Some(RouteType::Ferry),
+            RouteType::AerialLiftService
+            | RouteType::Telecabin
+            | RouteType::AerialCableCar
+            | RouteType::Elevator
+            | RouteType::ChairLift
+            | RouteType::DragLift
+            | RouteType::SmallTelecabin
+            | RouteType::AllTelecabinServices => Some(RouteType::Gondola),
+            RouteType::FunicularService => Some(RouteType::Funicular),
+            RouteType::CableCarService => Some(RouteType::CableCar),
+            _ => None,
+        }
+    }
 }
 
 impl<'de> serde::Deserialize<'de> for RouteType {
@@ -84,25 +522,22 @@ impl<'de> serde::Deserialize<'de> for RouteType {
     where
         D: serde::Deserializer<'de>,
     {
-        let result: u32 = try!(serde::Deserialize::deserialize(deserializer));
-        match result {
-            0 => Ok(RouteType::LightRail),
-            1 => Ok(RouteType::Subway),
-            2 => Ok(RouteType::Rail),
-            3 => Ok(RouteType::Bus),
-            4 => Ok(RouteType::Ferry),
-            5 => Ok(RouteType::CableCar),
-            6 => Ok(RouteType::Gondola),
-            7 => Ok(RouteType::Funicular),
-            _ => Err(serde::de::Error::custom(
-                "Route type must (currently) be 0-7",
-            )),
-        }
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        Ok(RouteType::from_code(result))
+    }
+}
+
+impl serde::Serialize for RouteType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(self.code())
     }
 }
 
 /// Route
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Route {
     pub route_id: String,
     pub agency_id: Option<String>,
@@ -113,6 +548,16 @@ pub struct Route {
     pub route_url: Option<String>,
     pub route_color: Option<String>,
     pub route_text_color: Option<String>,
+    /// Order in which routes are presented to riders, lowest first
+    pub route_sort_order: Option<u32>,
+    /// Whether riders can board anywhere along the route, blank for nowhere
+    ///
+    /// Can be overridden for parts of a trip by `StopTime::continuous_pickup`.
+    pub continuous_pickup: Option<StopServiceType>,
+    /// Whether riders can alight anywhere along the route, blank for nowhere
+    pub continuous_drop_off: Option<StopServiceType>,
+    /// GTFS-Fares v2 network, unless the route is listed in route_networks.txt
+    pub network_id: Option<String>,
 }
 
 /// Wheelchair Accessible
@@ -150,6 +595,19 @@ impl<'de> serde::Deserialize<'de> for WheelchairAccessible {
     }
 }
 
+impl serde::Serialize for WheelchairAccessible {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            WheelchairAccessible::NoInformation => 0,
+            WheelchairAccessible::SomeAccessibility => 1,
+            WheelchairAccessible::NoAccessibility => 2,
+        })
+    }
+}
+
 /// Bikes Allowed
 #[derive(Debug)]
 pub enum BikesAllowed {
@@ -184,8 +642,63 @@ impl<'de> serde::Deserialize<'de> for BikesAllowed {
     }
 }
 
+impl serde::Serialize for BikesAllowed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            BikesAllowed::NoInformation => 0,
+            BikesAllowed::SomeBikes => 1,
+            BikesAllowed::NoBikes => 2,
+        })
+    }
+}
+
+/// Cars Allowed
+#[derive(Debug, Default, PartialEq)]
+pub enum CarsAllowed {
+    #[default]
+    NoInformation,
+    SomeCars,
+    NoCars,
+}
+
+impl<'de> serde::Deserialize<'de> for CarsAllowed {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: String = serde::Deserialize::deserialize(deserializer)?;
+        match result.trim() {
+            "" => Ok(CarsAllowed::NoInformation),
+            r => match r.parse::<u32>() {
+                Ok(0) => Ok(CarsAllowed::NoInformation),
+                Ok(1) => Ok(CarsAllowed::SomeCars),
+                Ok(2) => Ok(CarsAllowed::NoCars),
+                _ => Err(serde::de::Error::custom(
+                    "Cars allowed must be between 0 and 2",
+                )),
+            },
+        }
+    }
+}
+
+impl serde::Serialize for CarsAllowed {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            CarsAllowed::NoInformation => 0,
+            CarsAllowed::SomeCars => 1,
+            CarsAllowed::NoCars => 2,
+        })
+    }
+}
+
 /// Trip
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Trip {
     pub route_id: String,
     pub service_id: String,
@@ -199,10 +712,12 @@ pub struct Trip {
     pub wheelchair_accessible: WheelchairAccessible,
     #[serde(default)]
     pub bikes_allowed: BikesAllowed,
+    #[serde(default)]
+    pub cars_allowed: CarsAllowed,
 }
 
-/// PickupType/DropoffType for `StopTime`
-#[derive(Debug, PartialEq)]
+/// PickupType/DropoffType for `StopTime`, also used for continuous pickup and drop off
+#[derive(Clone, Debug, PartialEq)]
 pub enum StopServiceType {
     RegularlyScheduled,
     NoServiceAvailable, // No pickup or dropoff available
@@ -237,8 +752,22 @@ impl<'de> serde::Deserialize<'de> for StopServiceType {
     }
 }
 
+impl serde::Serialize for StopServiceType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            StopServiceType::RegularlyScheduled => 0,
+            StopServiceType::NoServiceAvailable => 1,
+            StopServiceType::MustPhoneAgency => 2,
+            StopServiceType::MustCoordinateWithDriver => 3,
+        })
+    }
+}
+
 /// Timepoint for `StopTime`
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Timepoint {
     Approximate,
     Exact,
@@ -267,45 +796,101 @@ impl<'de> serde::Deserialize<'de> for Timepoint {
     }
 }
 
+impl serde::Serialize for Timepoint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            Timepoint::Approximate => 0,
+            Timepoint::Exact => 1,
+        })
+    }
+}
+
 /// StopTime
-#[derive(Debug, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct StopTime {
     pub trip_id: String,
-    pub arrival_time: TimeOffset,
-    pub departure_time: TimeOffset,
+    /// Blank for stops between timepoints, see `Run::interpolate`
+    pub arrival_time: Option<TimeOffset>,
+    /// Blank for stops between timepoints, see `Run::interpolate`
+    pub departure_time: Option<TimeOffset>,
+    /// Blank for GTFS-Flex rows served by a `location_group_id` or `location_id`
+    #[serde(default)]
     pub stop_id: String,
     pub stop_sequence: u64,
     pub stop_headsign: Option<String>,
     #[serde(default)]
     pub pickup_type: StopServiceType,
-    #[serde(default)]
+    #[serde(default, rename = "drop_off_type", alias = "dropoff_type")]
     pub dropoff_type: StopServiceType,
     pub shape_dist_traveled: Option<f64>,
     #[serde(default)]
     pub timepoint: Timepoint,
+    /// GTFS-Flex group of stops from location_groups.txt
+    pub location_group_id: Option<String>,
+    /// GTFS-Flex zone from locations.geojson
+    pub location_id: Option<String>,
+    /// Start of the GTFS-Flex window in which riders can be picked up or dropped off
+    pub start_pickup_drop_off_window: Option<TimeOffset>,
+    pub end_pickup_drop_off_window: Option<TimeOffset>,
+    pub pickup_booking_rule_id: Option<String>,
+    pub drop_off_booking_rule_id: Option<String>,
+    /// Whether riders can board between this stop and the next, blank for the route's
+    pub continuous_pickup: Option<StopServiceType>,
+    /// Whether riders can alight between this stop and the next, blank for the route's
+    pub continuous_drop_off: Option<StopServiceType>,
 }
 
 /// Calendar
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Calendar {
     pub service_id: String,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub monday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub tuesday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub wednesday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub thursday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub friday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub saturday: bool,
-    #[serde(deserialize_with = "deserialize_dow_field")]
+    #[serde(
+        deserialize_with = "deserialize_dow_field",
+        serialize_with = "serialize_dow_field"
+    )]
     pub sunday: bool,
-    #[serde(deserialize_with = "deserialize_calendardate")]
+    #[serde(
+        deserialize_with = "deserialize_calendardate",
+        serialize_with = "serialize_calendardate"
+    )]
     pub start_date: NaiveDate,
-    #[serde(deserialize_with = "deserialize_calendardate")]
+    #[serde(
+        deserialize_with = "deserialize_calendardate",
+        serialize_with = "serialize_calendardate"
+    )]
     pub end_date: NaiveDate,
 }
 
@@ -332,11 +917,26 @@ impl<'de> serde::Deserialize<'de> for ExceptionType {
     }
 }
 
+impl serde::Serialize for ExceptionType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            ExceptionType::ServiceAdded => 1,
+            ExceptionType::ServiceRemoved => 2,
+        })
+    }
+}
+
 /// CalendarDate
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CalendarDate {
     pub service_id: String,
-    #[serde(deserialize_with = "deserialize_calendardate")]
+    #[serde(
+        deserialize_with = "deserialize_calendardate",
+        serialize_with = "serialize_calendardate"
+    )]
     pub date: NaiveDate,
     pub exception_type: ExceptionType,
 }
@@ -362,6 +962,18 @@ impl<'de> serde::Deserialize<'de> for PaymentMethod {
     }
 }
 
+impl serde::Serialize for PaymentMethod {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            PaymentMethod::PaidOnboard => 0,
+            PaymentMethod::PaidBefore => 1,
+        })
+    }
+}
+
 /// Tranfers for `FareAttribute`
 #[derive(Debug)]
 pub enum Transfers {
@@ -391,21 +1003,38 @@ impl<'de> serde::Deserialize<'de> for Transfers {
     }
 }
 
+impl serde::Serialize for Transfers {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match *self {
+            Transfers::None => serializer.serialize_u32(0),
+            Transfers::TransferOnce => serializer.serialize_u32(1),
+            Transfers::TransferTwice => serializer.serialize_u32(2),
+            Transfers::Unlimited => serializer.serialize_str(""),
+        }
+    }
+}
+
 /// FareAttribute
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FareAttribute {
     pub fare_id: String,
     pub price: f64,
     pub currency_type: String,
     pub payment_method: PaymentMethod,
     pub transfers: Transfers,
-    #[serde(deserialize_with = "deserialize_transferduration")]
+    #[serde(
+        deserialize_with = "deserialize_transferduration",
+        serialize_with = "serialize_transferduration"
+    )]
     pub transfer_duration: Option<Duration>,
 }
 
 /// FareRule
 /// origin, destination, and contains reference a zone_id from stops
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FareRule {
     pub fare_id: String,
     pub route_id: Option<String>,
@@ -414,9 +1043,234 @@ pub struct FareRule {
     pub contains_id: Option<String>,
 }
 
+/// Fare Media Type
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FareMediaType {
+    /// No fare media is involved, e.g. cash paid to the driver
+    None,
+    PaperTicket,
+    TransitCard,
+    /// Contactless bank cards and devices
+    ContactlessEmv,
+    MobileApp,
+}
+
+impl<'de> serde::Deserialize<'de> for FareMediaType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        match result {
+            0 => Ok(FareMediaType::None),
+            1 => Ok(FareMediaType::PaperTicket),
+            2 => Ok(FareMediaType::TransitCard),
+            3 => Ok(FareMediaType::ContactlessEmv),
+            4 => Ok(FareMediaType::MobileApp),
+            _ => Err(serde::de::Error::custom(
+                "fare media type must be between 0 and 4",
+            )),
+        }
+    }
+}
+
+impl serde::Serialize for FareMediaType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            FareMediaType::None => 0,
+            FareMediaType::PaperTicket => 1,
+            FareMediaType::TransitCard => 2,
+            FareMediaType::ContactlessEmv => 3,
+            FareMediaType::MobileApp => 4,
+        })
+    }
+}
+
+/// FareMedia
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FareMedia {
+    pub fare_media_id: String,
+    pub fare_media_name: Option<String>,
+    pub fare_media_type: FareMediaType,
+}
+
+/// FareProduct
+///
+/// A product can have several rows, one for each fare media it is sold on.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FareProduct {
+    pub fare_product_id: String,
+    pub fare_product_name: Option<String>,
+    pub fare_media_id: Option<String>,
+    pub amount: f64,
+    pub currency: String,
+}
+
+/// FareLegRule
+/// network, areas and timeframes that a `FareProduct` applies to for a single leg
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FareLegRule {
+    pub leg_group_id: Option<String>,
+    pub network_id: Option<String>,
+    pub from_area_id: Option<String>,
+    pub to_area_id: Option<String>,
+    pub from_timeframe_group_id: Option<String>,
+    pub to_timeframe_group_id: Option<String>,
+    pub fare_product_id: String,
+    /// Rules with a higher priority win over lower ones that also match
+    pub rule_priority: Option<u32>,
+}
+
+/// Duration Limit Type for `FareTransferRule`
+///
+/// Which ends of the two legs the duration_limit is measured between.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DurationLimitType {
+    DepartureToArrival,
+    DepartureToDeparture,
+    ArrivalToDeparture,
+    ArrivalToArrival,
+}
+
+impl<'de> serde::Deserialize<'de> for DurationLimitType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        match result {
+            0 => Ok(DurationLimitType::DepartureToArrival),
+            1 => Ok(DurationLimitType::DepartureToDeparture),
+            2 => Ok(DurationLimitType::ArrivalToDeparture),
+            3 => Ok(DurationLimitType::ArrivalToArrival),
+            _ => Err(serde::de::Error::custom(
+                "duration limit type must be between 0 and 3",
+            )),
+        }
+    }
+}
+
+impl serde::Serialize for DurationLimitType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            DurationLimitType::DepartureToArrival => 0,
+            DurationLimitType::DepartureToDeparture => 1,
+            DurationLimitType::ArrivalToDeparture => 2,
+            DurationLimitType::ArrivalToArrival => 3,
+        })
+    }
+}
+
+/// Fare Transfer Type for `FareTransferRule`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FareTransferType {
+    /// The first leg's fare plus the transfer's fare product
+    FirstLegPlusTransfer,
+    /// Both legs' fares plus the transfer's fare product
+    BothLegsPlusTransfer,
+    /// Only the transfer's fare product
+    TransferOnly,
+}
+
+impl<'de> serde::Deserialize<'de> for FareTransferType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        match result {
+            0 => Ok(FareTransferType::FirstLegPlusTransfer),
+            1 => Ok(FareTransferType::BothLegsPlusTransfer),
+            2 => Ok(FareTransferType::TransferOnly),
+            _ => Err(serde::de::Error::custom(
+                "fare transfer type must be between 0 and 2",
+            )),
+        }
+    }
+}
+
+impl serde::Serialize for FareTransferType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            FareTransferType::FirstLegPlusTransfer => 0,
+            FareTransferType::BothLegsPlusTransfer => 1,
+            FareTransferType::TransferOnly => 2,
+        })
+    }
+}
+
+/// FareTransferRule
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FareTransferRule {
+    pub from_leg_group_id: Option<String>,
+    pub to_leg_group_id: Option<String>,
+    /// Number of transfers allowed, -1 for unlimited
+    pub transfer_count: Option<i32>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_transferduration",
+        serialize_with = "serialize_transferduration"
+    )]
+    pub duration_limit: Option<Duration>,
+    pub duration_limit_type: Option<DurationLimitType>,
+    pub fare_transfer_type: FareTransferType,
+    pub fare_product_id: Option<String>,
+}
+
+/// Area
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Area {
+    pub area_id: String,
+    pub area_name: Option<String>,
+}
+
+/// StopArea
+/// Assigns a stop to an `Area`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StopArea {
+    pub area_id: String,
+    pub stop_id: String,
+}
+
+/// Network
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Network {
+    pub network_id: String,
+    pub network_name: Option<String>,
+}
+
+/// RouteNetwork
+/// Assigns a route to a `Network`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RouteNetwork {
+    pub network_id: String,
+    pub route_id: String,
+}
+
+/// Timeframe
+/// Times of day on the days of a service that fare leg rules can depend on
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Timeframe {
+    pub timeframe_group_id: String,
+    /// Blank for the start of the day
+    pub start_time: Option<TimeOffset>,
+    /// Blank for the end of the day
+    pub end_time: Option<TimeOffset>,
+    pub service_id: String,
+}
+
 /// ShapePoint
 /// A row from shapes.txt
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct ShapePoint {
     pub shape_id: String,
     pub shape_pt_lat: f64,
@@ -425,7 +1279,12 @@ pub struct ShapePoint {
     pub shape_dist_traveled: Option<f64>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// Time of day relative to a service day
+///
+/// GTFS times are measured from "noon minus 12h" on the service date, which is
+/// midnight except on days with a daylight saving change. They can exceed 24:00:00
+/// for trips that run past midnight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TimeOffset {
     pub hours: u32,
     pub minutes: u32,
@@ -441,10 +1300,137 @@ impl TimeOffset {
         }
     }
 
+    /// Creates a normalized `TimeOffset` from seconds since the start of the service day
+    pub fn from_seconds(seconds: u32) -> TimeOffset {
+        TimeOffset::from_hms(seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    }
+
+    /// Seconds since the start of the service day
+    pub fn total_seconds(&self) -> u32 {
+        self.hours * 3600 + self.minutes * 60 + self.seconds
+    }
+
+    /// Carries minutes and seconds of 60 or more into the larger units
+    pub fn normalize(&self) -> TimeOffset {
+        TimeOffset::from_seconds(self.total_seconds())
+    }
+
     pub fn duration(&self) -> Duration {
-        Duration::hours(self.hours as i64) + Duration::minutes(self.minutes as i64)
+        Duration::hours(self.hours as i64)
+            + Duration::minutes(self.minutes as i64)
             + Duration::seconds(self.seconds as i64)
     }
+
+    /// Resolves this offset on `service_date` to a timestamp in `tz`
+    ///
+    /// The offset is counted from noon minus 12h so a trip keeps its scheduled
+    /// running time across daylight saving changes. Returns `None` if noon doesn't
+    /// exist or is ambiguous in `tz` on `service_date`.
+    pub fn to_datetime<Tz: TimeZone>(
+        &self,
+        service_date: NaiveDate,
+        tz: &Tz,
+    ) -> Option<DateTime<Tz>> {
+        let noon = tz
+            .from_local_datetime(&service_date.and_hms_opt(12, 0, 0)?)
+            .single()?;
+        Some(noon - Duration::hours(12) + self.duration())
+    }
+
+    fn from_signed_seconds(seconds: i64) -> TimeOffset {
+        TimeOffset::from_seconds(seconds.max(0).min(u32::MAX as i64) as u32)
+    }
+}
+
+/// Adds a `Duration`, saturating at 00:00:00 if the result would be negative
+impl Add<Duration> for TimeOffset {
+    type Output = TimeOffset;
+
+    fn add(self, rhs: Duration) -> TimeOffset {
+        TimeOffset::from_signed_seconds(self.total_seconds() as i64 + rhs.num_seconds())
+    }
+}
+
+impl AddAssign<Duration> for TimeOffset {
+    fn add_assign(&mut self, rhs: Duration) {
+        *self = *self + rhs;
+    }
+}
+
+/// Subtracts a `Duration`, saturating at 00:00:00 if the result would be negative
+impl Sub<Duration> for TimeOffset {
+    type Output = TimeOffset;
+
+    fn sub(self, rhs: Duration) -> TimeOffset {
+        TimeOffset::from_signed_seconds(self.total_seconds() as i64 - rhs.num_seconds())
+    }
+}
+
+impl SubAssign<Duration> for TimeOffset {
+    fn sub_assign(&mut self, rhs: Duration) {
+        *self = *self - rhs;
+    }
+}
+
+/// Time elapsed between two offsets on the same service day
+impl Sub for TimeOffset {
+    type Output = Duration;
+
+    fn sub(self, rhs: TimeOffset) -> Duration {
+        Duration::seconds(self.total_seconds() as i64 - rhs.total_seconds() as i64)
+    }
+}
+
+impl fmt::Display for TimeOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time = self.normalize();
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            time.hours, time.minutes, time.seconds
+        )
+    }
+}
+
+/// Error from parsing a `TimeOffset` that isn't in H:MM:SS format
+#[derive(Debug, PartialEq)]
+pub struct ParseTimeOffsetError {
+    message: String,
+}
+
+impl fmt::Display for ParseTimeOffsetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl StdError for ParseTimeOffsetError {}
+
+impl FromStr for TimeOffset {
+    type Err = ParseTimeOffsetError;
+
+    fn from_str(s: &str) -> Result<TimeOffset, ParseTimeOffsetError> {
+        let mut parts = s.trim().split(':');
+        let mut parse_part = || -> Result<u32, ParseTimeOffsetError> {
+            match parts.next() {
+                Some(val) => val.parse().map_err(|e| ParseTimeOffsetError {
+                    message: format!("{}", e),
+                }),
+                None => Err(ParseTimeOffsetError {
+                    message: "Unexpected timeoffset part".to_string(),
+                }),
+            }
+        };
+        let hours = parse_part()?;
+        let minutes = parse_part()?;
+        let seconds = parse_part()?;
+        match parts.next() {
+            Some(_) => Err(ParseTimeOffsetError {
+                message: "Unexpected timeoffset part".to_string(),
+            }),
+            None => Ok(TimeOffset::from_hms(hours, minutes, seconds)),
+        }
+    }
 }
 
 impl<'de> serde::Deserialize<'de> for TimeOffset {
@@ -453,25 +1439,21 @@ impl<'de> serde::Deserialize<'de> for TimeOffset {
         D: serde::Deserializer<'de>,
     {
         let result: String = try!(serde::Deserialize::deserialize(deserializer));
-        let mut parts = result.trim().split(':');
-        let parse_part = |part: Option<&str>| -> Result<u32, D::Error> {
-            match part {
-                Some(val) => match val.parse() {
-                    Ok(x) => Ok(x),
-                    Err(y) => Err(serde::de::Error::custom(y)),
-                },
-                None => Err(serde::de::Error::custom("Unexpected timeoffset part")),
-            }
-        };
-        let hours = try!(parse_part(parts.next()));
-        let minutes = try!(parse_part(parts.next()));
-        let seconds = try!(parse_part(parts.next()));
-        Ok(TimeOffset::from_hms(hours, minutes, seconds))
+        result.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for TimeOffset {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
     }
 }
 
 /// exact_times for Frequency
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum FrequencyAccuracy {
     Approximate,
     Exact,
@@ -502,8 +1484,20 @@ impl<'de> serde::Deserialize<'de> for FrequencyAccuracy {
     }
 }
 
+impl serde::Serialize for FrequencyAccuracy {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            FrequencyAccuracy::Approximate => 0,
+            FrequencyAccuracy::Exact => 1,
+        })
+    }
+}
+
 /// Frequency
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Frequency {
     pub trip_id: String,
     pub start_time: TimeOffset,
@@ -539,29 +1533,418 @@ impl<'de> serde::Deserialize<'de> for TransferType {
     }
 }
 
+impl serde::Serialize for TransferType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            TransferType::Recommended => 0,
+            TransferType::Timed => 1,
+            TransferType::MinimumTime => 2,
+            TransferType::NotPossible => 3,
+        })
+    }
+}
+
 /// Transfer
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct Transfer {
     pub from_stop_id: String,
     pub to_stop_id: String,
     pub transfer_type: TransferType,
-    #[serde(deserialize_with = "deserialize_transferduration")]
+    #[serde(
+        deserialize_with = "deserialize_transferduration",
+        serialize_with = "serialize_transferduration"
+    )]
     pub min_transfer_time: Option<Duration>,
 }
 
+/// Pathway Mode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PathwayMode {
+    Walkway,
+    Stairs,
+    MovingSidewalk,
+    Escalator,
+    Elevator,
+    /// Gate into the paid area of a station
+    FareGate,
+    /// Gate out of the paid area of a station
+    ExitGate,
+}
+
+impl PathwayMode {
+    /// True if the pathway can be used without climbing steps
+    pub fn is_step_free(&self) -> bool {
+        !matches!(*self, PathwayMode::Stairs | PathwayMode::Escalator)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for PathwayMode {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        match result {
+            1 => Ok(PathwayMode::Walkway),
+            2 => Ok(PathwayMode::Stairs),
+            3 => Ok(PathwayMode::MovingSidewalk),
+            4 => Ok(PathwayMode::Escalator),
+            5 => Ok(PathwayMode::Elevator),
+            6 => Ok(PathwayMode::FareGate),
+            7 => Ok(PathwayMode::ExitGate),
+            _ => Err(serde::de::Error::custom(
+                "pathway mode must be between 1 and 7",
+            )),
+        }
+    }
+}
+
+impl serde::Serialize for PathwayMode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            PathwayMode::Walkway => 1,
+            PathwayMode::Stairs => 2,
+            PathwayMode::MovingSidewalk => 3,
+            PathwayMode::Escalator => 4,
+            PathwayMode::Elevator => 5,
+            PathwayMode::FareGate => 6,
+            PathwayMode::ExitGate => 7,
+        })
+    }
+}
+
+/// Pathway
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Pathway {
+    pub pathway_id: String,
+    pub from_stop_id: String,
+    pub to_stop_id: String,
+    pub pathway_mode: PathwayMode,
+    #[serde(
+        deserialize_with = "deserialize_bool_field",
+        serialize_with = "serialize_bool_field"
+    )]
+    pub is_bidirectional: bool,
+    /// Length in metres
+    pub length: Option<f64>,
+    /// Seconds needed to walk the pathway
+    pub traversal_time: Option<u64>,
+    /// Number of stairs, negative when going down from `from_stop_id`
+    pub stair_count: Option<i64>,
+    pub max_slope: Option<f64>,
+    pub min_width: Option<f64>,
+    pub signposted_as: Option<String>,
+    pub reversed_signposted_as: Option<String>,
+}
+
+/// Level
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Level {
+    pub level_id: String,
+    /// Position relative to the street, 0 is ground level and negative is below
+    pub level_index: f64,
+    pub level_name: Option<String>,
+}
+
 /// Feed Info
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct FeedInfo {
     pub feed_publisher_name: String,
     pub feed_publisher_url: String,
     pub feed_lang: String,
-    #[serde(default = "default_feed_date", deserialize_with = "deserialize_option_calendardate")]
+    #[serde(
+        default = "default_feed_date",
+        deserialize_with = "deserialize_option_calendardate",
+        serialize_with = "serialize_option_calendardate"
+    )]
     pub feed_start_date: Option<NaiveDate>,
-    #[serde(default = "default_feed_date", deserialize_with = "deserialize_option_calendardate")]
+    #[serde(
+        default = "default_feed_date",
+        deserialize_with = "deserialize_option_calendardate",
+        serialize_with = "serialize_option_calendardate"
+    )]
     pub feed_end_date: Option<NaiveDate>,
     pub feed_version: Option<String>,
 }
 
+/// LocationGroup
+/// GTFS-Flex group of stops served together
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationGroup {
+    pub location_group_id: String,
+    pub location_group_name: Option<String>,
+}
+
+/// LocationGroupStop
+/// Assigns a stop to a `LocationGroup`
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocationGroupStop {
+    pub location_group_id: String,
+    pub stop_id: String,
+}
+
+/// Booking Type for `BookingRule`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BookingType {
+    /// Booked in real time
+    RealTime,
+    /// Booked the same day with advance notice
+    SameDay,
+    /// Booked up to a number of days before travel
+    PriorDays,
+}
+
+impl<'de> serde::Deserialize<'de> for BookingType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let result: u32 = serde::Deserialize::deserialize(deserializer)?;
+        match result {
+            0 => Ok(BookingType::RealTime),
+            1 => Ok(BookingType::SameDay),
+            2 => Ok(BookingType::PriorDays),
+            _ => Err(serde::de::Error::custom(
+                "booking type must be between 0 and 2",
+            )),
+        }
+    }
+}
+
+impl serde::Serialize for BookingType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_u32(match *self {
+            BookingType::RealTime => 0,
+            BookingType::SameDay => 1,
+            BookingType::PriorDays => 2,
+        })
+    }
+}
+
+/// BookingRule
+/// How and when a GTFS-Flex service has to be booked
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BookingRule {
+    pub booking_rule_id: String,
+    pub booking_type: BookingType,
+    /// Minimum minutes of notice for same day bookings
+    pub prior_notice_duration_min: Option<u32>,
+    /// Maximum minutes of notice for same day bookings
+    pub prior_notice_duration_max: Option<u32>,
+    /// Latest day before travel to book, for prior day bookings
+    pub prior_notice_last_day: Option<u32>,
+    pub prior_notice_last_time: Option<TimeOffset>,
+    /// Earliest day before travel to book
+    pub prior_notice_start_day: Option<u32>,
+    pub prior_notice_start_time: Option<TimeOffset>,
+    /// Service whose days are counted for the prior notice days
+    pub prior_notice_service_id: Option<String>,
+    pub message: Option<String>,
+    pub pickup_message: Option<String>,
+    pub drop_off_message: Option<String>,
+    pub phone_number: Option<String>,
+    pub info_url: Option<String>,
+    pub booking_url: Option<String>,
+}
+
+/// Attribution
+///
+/// Applies to the whole feed unless one of `agency_id`, `route_id` or `trip_id` is set.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Attribution {
+    pub attribution_id: Option<String>,
+    pub agency_id: Option<String>,
+    pub route_id: Option<String>,
+    pub trip_id: Option<String>,
+    pub organization_name: String,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_flag_field",
+        serialize_with = "serialize_bool_field"
+    )]
+    pub is_producer: bool,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_flag_field",
+        serialize_with = "serialize_bool_field"
+    )]
+    pub is_operator: bool,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_flag_field",
+        serialize_with = "serialize_bool_field"
+    )]
+    pub is_authority: bool,
+    pub attribution_url: Option<String>,
+    pub attribution_email: Option<String>,
+    pub attribution_phone: Option<String>,
+}
+
+/// Translation
+///
+/// Either `record_id` (with `record_sub_id` for stop_times.txt) or `field_value`
+/// selects what is translated.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Translation {
+    pub table_name: String,
+    pub field_name: String,
+    pub language: String,
+    pub translation: String,
+    pub record_id: Option<String>,
+    pub record_sub_id: Option<String>,
+    pub field_value: Option<String>,
+}
+
 fn default_feed_date() -> Option<NaiveDate> {
     None
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chrono::FixedOffset;
+    use chrono_tz::America::New_York;
+
+    #[test]
+    fn test_route_type_codes() {
+        for code in 0..2000 {
+            assert_eq!(code, RouteType::from_code(code).code());
+        }
+        assert_eq!(RouteType::Trolleybus, RouteType::from_code(11));
+        assert_eq!(RouteType::Monorail, RouteType::from_code(12));
+        assert_eq!(RouteType::HighSpeedRail, RouteType::from_code(101));
+        assert_eq!(RouteType::Other(8), RouteType::from_code(8));
+        assert_eq!(RouteType::CableCarService, RouteType::from_code(1701));
+        assert_eq!(RouteType::Elevator, RouteType::from_code(1303));
+    }
+
+    #[test]
+    fn test_route_type_basic_type() {
+        assert_eq!(Some(RouteType::Bus), RouteType::Bus.basic_type());
+        assert_eq!(Some(RouteType::Rail), RouteType::HighSpeedRail.basic_type());
+        assert_eq!(
+            Some(RouteType::Bus),
+            RouteType::InternationalCoach.basic_type()
+        );
+        assert_eq!(Some(RouteType::Subway), RouteType::Metro.basic_type());
+        assert_eq!(
+            Some(RouteType::Monorail),
+            RouteType::UrbanMonorail.basic_type()
+        );
+        assert_eq!(
+            Some(RouteType::Trolleybus),
+            RouteType::TrolleybusService.basic_type()
+        );
+        assert_eq!(Some(RouteType::LightRail), RouteType::CityTram.basic_type());
+        assert_eq!(
+            Some(RouteType::Ferry),
+            RouteType::WaterTransportService.basic_type()
+        );
+        assert_eq!(Some(RouteType::Gondola), RouteType::ChairLift.basic_type());
+        assert_eq!(Some(RouteType::Gondola), RouteType::Elevator.basic_type());
+        assert_eq!(
+            Some(RouteType::CableCar),
+            RouteType::CableCarService.basic_type()
+        );
+        assert_eq!(None, RouteType::HorseDrawnCarriage.basic_type());
+        assert_eq!(None, RouteType::AirService.basic_type());
+        assert_eq!(None, RouteType::Other(42).basic_type());
+    }
+
+    #[test]
+    fn test_parse_timeoffset() {
+        assert_eq!("01:01:01".parse(), Ok(TimeOffset::from_hms(1, 1, 1)));
+        assert_eq!("1:01:01".parse(), Ok(TimeOffset::from_hms(1, 1, 1)));
+        assert_eq!("01:01:01  ".parse(), Ok(TimeOffset::from_hms(1, 1, 1)));
+        assert_eq!(" 01:01:01  ".parse(), Ok(TimeOffset::from_hms(1, 1, 1)));
+        assert_eq!("25:30:00".parse(), Ok(TimeOffset::from_hms(25, 30, 0)));
+        assert!(":01:01".parse::<TimeOffset>().is_err());
+        assert!("ab:01:01".parse::<TimeOffset>().is_err());
+        assert!("01::01".parse::<TimeOffset>().is_err());
+        assert!("01:01".parse::<TimeOffset>().is_err());
+        assert!("01:01:01:01".parse::<TimeOffset>().is_err());
+    }
+
+    #[test]
+    fn test_display_timeoffset() {
+        assert_eq!("06:05:00", format!("{}", TimeOffset::from_hms(6, 5, 0)));
+        assert_eq!("25:10:03", format!("{}", TimeOffset::from_hms(25, 10, 3)));
+        assert_eq!("07:16:30", format!("{}", TimeOffset::from_hms(6, 75, 90)));
+    }
+
+    #[test]
+    fn test_timeoffset_seconds() {
+        let time = TimeOffset::from_hms(6, 75, 90);
+        assert_eq!(26190, time.total_seconds());
+        assert_eq!(TimeOffset::from_hms(7, 16, 30), time.normalize());
+        assert_eq!(
+            TimeOffset::from_hms(7, 16, 30),
+            TimeOffset::from_seconds(26190)
+        );
+    }
+
+    #[test]
+    fn test_timeoffset_arithmetic() {
+        let time = TimeOffset::from_hms(23, 50, 0);
+        assert_eq!(TimeOffset::from_hms(24, 5, 0), time + Duration::minutes(15));
+        assert_eq!(
+            TimeOffset::from_hms(23, 35, 0),
+            time - Duration::minutes(15)
+        );
+        assert_eq!(TimeOffset::from_hms(0, 0, 0), time - Duration::days(1));
+        let mut next = time;
+        next += Duration::seconds(90);
+        assert_eq!(TimeOffset::from_hms(23, 51, 30), next);
+        next -= Duration::hours(1);
+        assert_eq!(TimeOffset::from_hms(22, 51, 30), next);
+        assert_eq!(
+            Duration::minutes(-15),
+            TimeOffset::from_hms(23, 35, 0) - time
+        );
+        assert!(TimeOffset::from_hms(24, 5, 0) > time);
+    }
+
+    #[test]
+    fn test_timeoffset_to_datetime() {
+        let date = NaiveDate::from_ymd_opt(2026, 11, 3).unwrap();
+        let tz = FixedOffset::west_opt(5 * 3600).unwrap();
+        assert_eq!(
+            "2026-11-04T01:30:00-05:00",
+            TimeOffset::from_hms(25, 30, 0)
+                .to_datetime(date, &tz)
+                .unwrap()
+                .to_rfc3339()
+        );
+    }
+
+    #[test]
+    fn test_timeoffset_to_datetime_across_dst() {
+        // clocks go forward at 02:00 so the service day starts at 23:00 the day before
+        let spring = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+        let start = TimeOffset::from_hms(0, 0, 0)
+            .to_datetime(spring, &New_York)
+            .unwrap();
+        assert_eq!("2026-03-07T23:00:00-05:00", start.to_rfc3339());
+        let later = TimeOffset::from_hms(8, 0, 0)
+            .to_datetime(spring, &New_York)
+            .unwrap();
+        assert_eq!("2026-03-08T08:00:00-04:00", later.to_rfc3339());
+        assert_eq!(Duration::hours(8), later - start);
+
+        // clocks go back at 02:00 so the service day starts at 01:00
+        let autumn = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        let start = TimeOffset::from_hms(0, 0, 0)
+            .to_datetime(autumn, &New_York)
+            .unwrap();
+        assert_eq!("2026-11-01T01:00:00-04:00", start.to_rfc3339());
+    }
+}
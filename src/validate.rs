@@ -0,0 +1,870 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use feed::TransitFeed;
+use transit::LocationType;
+
+/// How serious a `Finding` is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The feed is usable but probably not what the producer intended
+    Warning,
+    /// The feed breaks the GTFS specification
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found by `validate`
+#[derive(Debug, PartialEq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub filename: String,
+    pub line: Option<u64>,
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(
+                f,
+                "{} in {} of {}:{} - {}",
+                self.severity, self.field, self.filename, line, self.message
+            ),
+            None => write!(
+                f,
+                "{} in {} of {} - {}",
+                self.severity, self.field, self.filename, self.message
+            ),
+        }
+    }
+}
+
+/// Checks that every reference between the records of `feed` can be resolved
+///
+/// Findings are returned in file order, use `Severity` to decide which ones
+/// should stop a feed from being published.
+pub fn validate(feed: &TransitFeed) -> Vec<Finding> {
+    let mut validator = Validator {
+        feed,
+        findings: vec![],
+    };
+    validator.check_routes();
+    validator.check_stops();
+    validator.check_trips();
+    validator.check_stop_times();
+    validator.check_frequencies();
+    validator.check_fare_rules();
+    validator.check_transfers();
+    validator.check_pathways();
+    validator.check_levels();
+    validator.findings
+}
+
+struct Validator<'a> {
+    feed: &'a TransitFeed,
+    findings: Vec<Finding>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(
+        &mut self,
+        severity: Severity,
+        filename: &str,
+        index: usize,
+        field: &str,
+        message: String,
+    ) {
+        self.findings.push(Finding {
+            severity,
+            filename: filename.to_string(),
+            line: self.feed.record_line(filename, index),
+            field: field.to_string(),
+            message,
+        });
+    }
+
+    fn check_routes(&mut self) {
+        let agencies: HashSet<&str> = self
+            .feed
+            .agencies
+            .iter()
+            .filter_map(|agency| agency.agency_id.as_deref())
+            .collect();
+        for (index, route) in self.feed.routes.iter().enumerate() {
+            match route.agency_id {
+                Some(ref agency_id) if !agencies.contains(agency_id.as_str()) => self.report(
+                    Severity::Error,
+                    "routes.txt",
+                    index,
+                    "agency_id",
+                    format!("agency {} does not exist in agency.txt", agency_id),
+                ),
+                None if self.feed.agencies.len() > 1 => self.report(
+                    Severity::Error,
+                    "routes.txt",
+                    index,
+                    "agency_id",
+                    format!(
+                        "route {} must have an agency_id when there are multiple agencies",
+                        route.route_id
+                    ),
+                ),
+                _ => (),
+            }
+        }
+    }
+
+    fn check_stops(&mut self) {
+        for (index, stop) in self.feed.stops.iter().enumerate() {
+            let located = match stop.location_type {
+                LocationType::Stop | LocationType::Station | LocationType::Entrance => true,
+                LocationType::GenericNode | LocationType::BoardingArea => false,
+            };
+            if located {
+                let required = [
+                    ("stop_name", stop.stop_name.is_some()),
+                    ("stop_lat", stop.stop_lat.is_some()),
+                    ("stop_lon", stop.stop_lon.is_some()),
+                ];
+                for &(field, present) in required.iter() {
+                    if !present {
+                        self.report(
+                            Severity::Error,
+                            "stops.txt",
+                            index,
+                            field,
+                            format!("{} is required for stops, stations and entrances", field),
+                        );
+                    }
+                }
+            }
+            // location type the parent must have, None if there can't be a parent
+            let (parent_type, required) = match stop.location_type {
+                LocationType::Stop => (Some(LocationType::Station), false),
+                LocationType::Station => (None, false),
+                LocationType::Entrance | LocationType::GenericNode => {
+                    (Some(LocationType::Station), true)
+                }
+                LocationType::BoardingArea => (Some(LocationType::Stop), true),
+            };
+            let parent_id = match (stop.parent_station.as_ref(), parent_type) {
+                (Some(_), None) => {
+                    self.report(
+                        Severity::Error,
+                        "stops.txt",
+                        index,
+                        "parent_station",
+                        "stations cannot have a parent_station".to_string(),
+                    );
+                    continue;
+                }
+                (None, Some(_)) if required => {
+                    self.report(
+                        Severity::Error,
+                        "stops.txt",
+                        index,
+                        "parent_station",
+                        format!(
+                            "{} must have a parent_station",
+                            match stop.location_type {
+                                LocationType::Entrance => "entrances",
+                                LocationType::GenericNode => "generic nodes",
+                                _ => "boarding areas",
+                            }
+                        ),
+                    );
+                    continue;
+                }
+                (Some(parent_id), Some(_)) => parent_id,
+                _ => continue,
+            };
+            match self.feed.find_stop(parent_id) {
+                None => self.report(
+                    Severity::Error,
+                    "stops.txt",
+                    index,
+                    "parent_station",
+                    format!("stop {} does not exist in stops.txt", parent_id),
+                ),
+                Some(parent) if Some(parent.location_type) != parent_type => self.report(
+                    Severity::Error,
+                    "stops.txt",
+                    index,
+                    "parent_station",
+                    match stop.location_type {
+                        LocationType::BoardingArea => {
+                            format!("stop {} is not a platform", parent_id)
+                        }
+                        _ => format!("stop {} is not a station", parent_id),
+                    },
+                ),
+                _ => (),
+            }
+        }
+    }
+
+    fn check_trips(&mut self) {
+        let mut services: HashSet<&str> = self
+            .feed
+            .calendars
+            .iter()
+            .map(|calendar| calendar.service_id.as_str())
+            .collect();
+        if let Some(ref calendar_dates) = self.feed.calendar_dates {
+            services.extend(calendar_dates.iter().map(|date| date.service_id.as_str()));
+        }
+        let shapes: HashSet<&str> = match self.feed.shapes {
+            Some(ref shapes) => shapes.iter().map(|point| point.shape_id.as_str()).collect(),
+            None => HashSet::new(),
+        };
+
+        for (index, trip) in self.feed.trips.iter().enumerate() {
+            if self.feed.find_route(&trip.route_id).is_none() {
+                self.report(
+                    Severity::Error,
+                    "trips.txt",
+                    index,
+                    "route_id",
+                    format!("route {} does not exist in routes.txt", trip.route_id),
+                );
+            }
+            if !services.contains(trip.service_id.as_str()) {
+                self.report(
+                    Severity::Error,
+                    "trips.txt",
+                    index,
+                    "service_id",
+                    format!(
+                        "service {} does not exist in calendar.txt or calendar_dates.txt",
+                        trip.service_id
+                    ),
+                );
+            }
+            if let Some(ref shape_id) = trip.shape_id {
+                if !shapes.contains(shape_id.as_str()) {
+                    self.report(
+                        Severity::Error,
+                        "trips.txt",
+                        index,
+                        "shape_id",
+                        format!("shape {} does not exist in shapes.txt", shape_id),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_stop_times(&mut self) {
+        let location_groups: HashSet<&str> = match self.feed.location_groups {
+            Some(ref groups) => groups
+                .iter()
+                .map(|group| group.location_group_id.as_str())
+                .collect(),
+            None => HashSet::new(),
+        };
+        let locations: HashSet<&str> = match self.feed.locations {
+            Some(ref locations) => locations
+                .iter()
+                .map(|location| location.location_id.as_str())
+                .collect(),
+            None => HashSet::new(),
+        };
+        let booking_rules: HashSet<&str> = match self.feed.booking_rules {
+            Some(ref rules) => rules
+                .iter()
+                .map(|rule| rule.booking_rule_id.as_str())
+                .collect(),
+            None => HashSet::new(),
+        };
+        for (index, stop_time) in self.feed.stoptimes.iter().enumerate() {
+            if self.feed.find_trip(&stop_time.trip_id).is_none() {
+                self.report(
+                    Severity::Error,
+                    "stop_times.txt",
+                    index,
+                    "trip_id",
+                    format!("trip {} does not exist in trips.txt", stop_time.trip_id),
+                );
+            }
+            if let Some(ref location_group_id) = stop_time.location_group_id {
+                if !location_groups.contains(location_group_id.as_str()) {
+                    self.report(
+                        Severity::Error,
+                        "stop_times.txt",
+                        index,
+                        "location_group_id",
+                        format!(
+                            "location group {} does not exist in location_groups.txt",
+                            location_group_id
+                        ),
+                    );
+                }
+            } else if let Some(ref location_id) = stop_time.location_id {
+                if !locations.contains(location_id.as_str()) {
+                    self.report(
+                        Severity::Error,
+                        "stop_times.txt",
+                        index,
+                        "location_id",
+                        format!(
+                            "location {} does not exist in locations.geojson",
+                            location_id
+                        ),
+                    );
+                }
+            } else if stop_time.stop_id.is_empty() {
+                self.report(
+                    Severity::Error,
+                    "stop_times.txt",
+                    index,
+                    "stop_id",
+                    "one of stop_id, location_group_id or location_id is required".to_string(),
+                );
+            } else if self.feed.find_stop(&stop_time.stop_id).is_none() {
+                self.report(
+                    Severity::Error,
+                    "stop_times.txt",
+                    index,
+                    "stop_id",
+                    format!("stop {} does not exist in stops.txt", stop_time.stop_id),
+                );
+            }
+            let booking_rule_fields = [
+                ("pickup_booking_rule_id", &stop_time.pickup_booking_rule_id),
+                (
+                    "drop_off_booking_rule_id",
+                    &stop_time.drop_off_booking_rule_id,
+                ),
+            ];
+            for &(field, booking_rule_id) in booking_rule_fields.iter() {
+                if let Some(ref booking_rule_id) = *booking_rule_id {
+                    if !booking_rules.contains(booking_rule_id.as_str()) {
+                        self.report(
+                            Severity::Error,
+                            "stop_times.txt",
+                            index,
+                            field,
+                            format!(
+                                "booking rule {} does not exist in booking_rules.txt",
+                                booking_rule_id
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_frequencies(&mut self) {
+        let frequencies = match self.feed.frequencies {
+            Some(ref frequencies) => frequencies,
+            None => return,
+        };
+        for (index, frequency) in frequencies.iter().enumerate() {
+            if self.feed.find_trip(&frequency.trip_id).is_none() {
+                self.report(
+                    Severity::Error,
+                    "frequencies.txt",
+                    index,
+                    "trip_id",
+                    format!("trip {} does not exist in trips.txt", frequency.trip_id),
+                );
+            }
+        }
+    }
+
+    fn check_fare_rules(&mut self) {
+        let fare_rules = match self.feed.fare_rules {
+            Some(ref fare_rules) => fare_rules,
+            None => return,
+        };
+        let fares: HashSet<&str> = match self.feed.fare_attributes {
+            Some(ref fares) => fares.iter().map(|fare| fare.fare_id.as_str()).collect(),
+            None => HashSet::new(),
+        };
+        let zones: HashSet<&str> = self
+            .feed
+            .stops
+            .iter()
+            .filter_map(|stop| stop.zone_id.as_deref())
+            .collect();
+
+        for (index, rule) in fare_rules.iter().enumerate() {
+            if !fares.contains(rule.fare_id.as_str()) {
+                self.report(
+                    Severity::Error,
+                    "fare_rules.txt",
+                    index,
+                    "fare_id",
+                    format!(
+                        "fare {} does not exist in fare_attributes.txt",
+                        rule.fare_id
+                    ),
+                );
+            }
+            if let Some(ref route_id) = rule.route_id {
+                if self.feed.find_route(route_id).is_none() {
+                    self.report(
+                        Severity::Error,
+                        "fare_rules.txt",
+                        index,
+                        "route_id",
+                        format!("route {} does not exist in routes.txt", route_id),
+                    );
+                }
+            }
+            let zone_fields = [
+                ("origin_id", &rule.origin_id),
+                ("destination_id", &rule.destination_id),
+                ("contains_id", &rule.contains_id),
+            ];
+            for &(field, zone_id) in zone_fields.iter() {
+                if let Some(ref zone_id) = *zone_id {
+                    if !zones.contains(zone_id.as_str()) {
+                        self.report(
+                            Severity::Error,
+                            "fare_rules.txt",
+                            index,
+                            field,
+                            format!("zone {} is not used by any stop in stops.txt", zone_id),
+                        );
+                    }
+                }
+            }
+        }
+
+        // a zone no rule mentions can't change which fare applies
+        let mut referenced: HashSet<&str> = HashSet::new();
+        for rule in fare_rules {
+            referenced.extend(rule.origin_id.as_deref());
+            referenced.extend(rule.destination_id.as_deref());
+            referenced.extend(rule.contains_id.as_deref());
+        }
+        let mut reported = HashSet::new();
+        for (index, stop) in self.feed.stops.iter().enumerate() {
+            if let Some(zone_id) = stop.zone_id.as_deref() {
+                if !referenced.contains(zone_id) && reported.insert(zone_id) {
+                    self.report(
+                        Severity::Warning,
+                        "stops.txt",
+                        index,
+                        "zone_id",
+                        format!("zone {} is not used by any rule in fare_rules.txt", zone_id),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_transfers(&mut self) {
+        let transfers = match self.feed.transfers {
+            Some(ref transfers) => transfers,
+            None => return,
+        };
+        for (index, transfer) in transfers.iter().enumerate() {
+            let stop_fields = [
+                ("from_stop_id", &transfer.from_stop_id),
+                ("to_stop_id", &transfer.to_stop_id),
+            ];
+            for &(field, stop_id) in stop_fields.iter() {
+                if self.feed.find_stop(stop_id).is_none() {
+                    self.report(
+                        Severity::Error,
+                        "transfers.txt",
+                        index,
+                        field,
+                        format!("stop {} does not exist in stops.txt", stop_id),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_pathways(&mut self) {
+        let pathways = match self.feed.pathways {
+            Some(ref pathways) => pathways,
+            None => return,
+        };
+        for (index, pathway) in pathways.iter().enumerate() {
+            let stop_fields = [
+                ("from_stop_id", &pathway.from_stop_id),
+                ("to_stop_id", &pathway.to_stop_id),
+            ];
+            for &(field, stop_id) in stop_fields.iter() {
+                if self.feed.find_stop(stop_id).is_none() {
+                    self.report(
+                        Severity::Error,
+                        "pathways.txt",
+                        index,
+                        field,
+                        format!("stop {} does not exist in stops.txt", stop_id),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_levels(&mut self) {
+        let levels: HashSet<&str> = match self.feed.levels {
+            Some(ref levels) => levels.iter().map(|level| level.level_id.as_str()).collect(),
+            None => HashSet::new(),
+        };
+        for (index, stop) in self.feed.stops.iter().enumerate() {
+            if let Some(ref level_id) = stop.level_id {
+                if !levels.contains(level_id.as_str()) {
+                    self.report(
+                        Severity::Error,
+                        "stops.txt",
+                        index,
+                        "level_id",
+                        format!("level {} does not exist in levels.txt", level_id),
+                    );
+                }
+            }
+        }
+
+        let used: HashSet<&str> = self
+            .feed
+            .stops
+            .iter()
+            .filter_map(|stop| stop.level_id.as_deref())
+            .collect();
+        let feed = self.feed;
+        for (index, level) in feed.levels.iter().flatten().enumerate() {
+            if !used.contains(level.level_id.as_str()) {
+                self.report(
+                    Severity::Warning,
+                    "levels.txt",
+                    index,
+                    "level_id",
+                    format!(
+                        "level {} is not used by any stop in stops.txt",
+                        level.level_id
+                    ),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use feed::{FeedReader, MemoryFeedProvider};
+
+    fn feed(files: Vec<(&'static str, &'static str)>) -> TransitFeed {
+        let mut provider: Vec<(&str, &str)> = vec![
+            (
+                "agency.txt",
+                "agency_id,agency_name,agency_url,agency_timezone\n\
+                 A,Demo,http://example.com,UTC\n",
+            ),
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,zone_id,location_type,parent_station\n\
+                 STATION,Station,1.0,1.0,,1,\n\
+                 S1,One,1.0,1.0,Z1,0,STATION\n\
+                 S2,Two,2.0,2.0,Z2,0,\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,agency_id,route_short_name,route_long_name,route_type\n\
+                 R,A,1,One,3\n",
+            ),
+            (
+                "trips.txt",
+                "route_id,service_id,trip_id,shape_id\n\
+                 R,WEEK,T,\n",
+            ),
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                 T,08:00:00,08:00:00,S1,1\n\
+                 T,08:10:00,08:10:00,S2,2\n",
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,\
+                 start_date,end_date\n\
+                 WEEK,1,1,1,1,1,0,0,20260101,20261231\n",
+            ),
+        ];
+        for (name, contents) in files {
+            provider.retain(|&(existing, _)| existing != name);
+            provider.push((name, contents));
+        }
+        let provider: MemoryFeedProvider = provider.into_iter().collect();
+        TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap()
+    }
+
+    fn messages(findings: &[Finding]) -> Vec<String> {
+        findings.iter().map(|f| format!("{}", f)).collect()
+    }
+
+    #[test]
+    fn test_valid_feed() {
+        assert_eq!(Vec::<Finding>::new(), validate(&feed(vec![])));
+    }
+
+    #[test]
+    fn test_dangling_trip_references() {
+        let feed = feed(vec![(
+            "trips.txt",
+            "route_id,service_id,trip_id,shape_id\n\
+             R,WEEK,T,\n\
+             X,SUNDAY,U,SHAPE\n",
+        )]);
+        assert_eq!(
+            vec![
+                "error in route_id of trips.txt:3 - route X does not exist in routes.txt",
+                "error in service_id of trips.txt:3 - service SUNDAY does not exist in \
+                 calendar.txt or calendar_dates.txt",
+                "error in shape_id of trips.txt:3 - shape SHAPE does not exist in shapes.txt",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_dangling_stop_time_references() {
+        let feed = feed(vec![(
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T,08:00:00,08:00:00,S1,1\n\
+             U,08:10:00,08:10:00,S3,2\n",
+        )]);
+        let findings = validate(&feed);
+        assert_eq!(
+            vec![
+                "error in trip_id of stop_times.txt:3 - trip U does not exist in trips.txt",
+                "error in stop_id of stop_times.txt:3 - stop S3 does not exist in stops.txt",
+            ],
+            messages(&findings)
+        );
+        assert!(findings.iter().all(|f| f.severity == Severity::Error));
+    }
+
+    #[test]
+    fn test_parent_station_must_be_station() {
+        let feed = feed(vec![(
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+             S1,One,1.0,1.0,0,S2\n\
+             S2,Two,2.0,2.0,0,MISSING\n",
+        )]);
+        assert_eq!(
+            vec![
+                "error in parent_station of stops.txt:2 - stop S2 is not a station",
+                "error in parent_station of stops.txt:3 - stop MISSING does not exist in stops.txt",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_stop_name_and_position_required() {
+        let feed = feed(vec![(
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon,zone_id,location_type,parent_station\n\
+             STATION,Station,1.0,1.0,,1,\n\
+             S1,One,1.0,,Z1,0,STATION\n\
+             S2,Two,2.0,2.0,Z2,0,\n\
+             N,,,,,3,STATION\n",
+        )]);
+        assert_eq!(
+            vec![
+                "error in stop_lon of stops.txt:3 - stop_lon is required for stops, stations \
+                 and entrances",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_parent_station_rules_by_location_type() {
+        let feed = feed(vec![(
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+             STATION,Station,1.0,1.0,1,\n\
+             P1,Platform,1.0,1.0,0,STATION\n\
+             E1,Entrance,1.0,1.0,2,STATION\n\
+             B1,Boarding,1.0,1.0,4,P1\n\
+             E2,Entrance,1.0,1.0,2,\n\
+             N1,Node,1.0,1.0,3,P1\n\
+             B2,Boarding,1.0,1.0,4,STATION\n\
+             NESTED,Station,1.0,1.0,1,STATION\n\
+             S1,One,1.0,1.0,0,\n\
+             S2,Two,2.0,2.0,0,\n",
+        )]);
+        assert_eq!(
+            vec![
+                "error in parent_station of stops.txt:6 - entrances must have a parent_station",
+                "error in parent_station of stops.txt:7 - stop P1 is not a station",
+                "error in parent_station of stops.txt:8 - stop STATION is not a platform",
+                "error in parent_station of stops.txt:9 - stations cannot have a parent_station",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_pathway_and_level_references() {
+        let feed = feed(vec![
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station,level_id\n\
+                 STATION,Station,1.0,1.0,1,,\n\
+                 S1,One,1.0,1.0,0,STATION,L0\n\
+                 S2,Two,2.0,2.0,0,,L9\n",
+            ),
+            (
+                "pathways.txt",
+                "pathway_id,from_stop_id,to_stop_id,pathway_mode,is_bidirectional\n\
+                 P1,S1,MISSING,1,1\n",
+            ),
+            ("levels.txt", "level_id,level_index\nL0,0\n"),
+        ]);
+        assert_eq!(
+            vec![
+                "error in to_stop_id of pathways.txt:2 - stop MISSING does not exist in stops.txt",
+                "error in level_id of stops.txt:4 - level L9 does not exist in levels.txt",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_flex_stop_time_references() {
+        let feed = feed(vec![
+            (
+                "stop_times.txt",
+                "trip_id,arrival_time,departure_time,stop_id,stop_sequence,location_group_id,\
+                 location_id,start_pickup_drop_off_window,end_pickup_drop_off_window,\
+                 pickup_booking_rule_id\n\
+                 T,,,,1,GROUP,,08:00:00,10:00:00,CALL\n\
+                 T,,,,2,,MISSING,08:00:00,10:00:00,\n\
+                 T,,,,3,,,08:00:00,10:00:00,LATE\n",
+            ),
+            ("location_groups.txt", "location_group_id\nGROUP\n"),
+            (
+                "location_group_stops.txt",
+                "location_group_id,stop_id\nGROUP,S1\n",
+            ),
+            (
+                "booking_rules.txt",
+                "booking_rule_id,booking_type\nCALL,0\n",
+            ),
+        ]);
+        assert_eq!(
+            vec![
+                "error in location_id of stop_times.txt:3 - location MISSING does not exist in \
+                 locations.geojson",
+                "error in stop_id of stop_times.txt:4 - one of stop_id, location_group_id or \
+                 location_id is required",
+                "error in pickup_booking_rule_id of stop_times.txt:4 - booking rule LATE does \
+                 not exist in booking_rules.txt",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_route_agency_required_with_multiple_agencies() {
+        let feed = feed(vec![
+            (
+                "agency.txt",
+                "agency_id,agency_name,agency_url,agency_timezone\n\
+                 A,Demo,http://example.com,UTC\n\
+                 B,Other,http://example.com,UTC\n",
+            ),
+            (
+                "routes.txt",
+                "route_id,agency_id,route_short_name,route_long_name,route_type\n\
+                 R,,1,One,3\n",
+            ),
+        ]);
+        assert_eq!(
+            vec![
+                "error in agency_id of routes.txt:2 - route R must have an agency_id when there \
+                 are multiple agencies",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_fare_rule_and_transfer_references() {
+        let feed = feed(vec![
+            (
+                "fare_attributes.txt",
+                "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+                 F,1.00,USD,0,,\n",
+            ),
+            (
+                "fare_rules.txt",
+                "fare_id,route_id,origin_id,destination_id,contains_id\n\
+                 F,R,Z1,Z2,\n\
+                 F,,Z1,Z9,\n",
+            ),
+            (
+                "transfers.txt",
+                "from_stop_id,to_stop_id,transfer_type,min_transfer_time\n\
+                 S1,S9,2,60\n",
+            ),
+        ]);
+        assert_eq!(
+            vec![
+                "error in destination_id of fare_rules.txt:3 - zone Z9 is not used by any stop \
+                 in stops.txt",
+                "error in to_stop_id of transfers.txt:2 - stop S9 does not exist in stops.txt",
+            ],
+            messages(&validate(&feed))
+        );
+    }
+
+    #[test]
+    fn test_unreferenced_levels_and_zones() {
+        let feed = feed(vec![
+            (
+                "stops.txt",
+                "stop_id,stop_name,stop_lat,stop_lon,zone_id,location_type,parent_station,\
+                 level_id\n\
+                 STATION,Station,1.0,1.0,,1,,\n\
+                 S1,One,1.0,1.0,Z1,0,STATION,L0\n\
+                 S2,Two,2.0,2.0,Z2,0,,\n\
+                 S3,Three,3.0,3.0,Z2,0,,\n",
+            ),
+            ("levels.txt", "level_id,level_index\nL0,0\nL1,1\n"),
+            (
+                "fare_attributes.txt",
+                "fare_id,price,currency_type,payment_method,transfers,transfer_duration\n\
+                 F,1.00,USD,0,,\n",
+            ),
+            (
+                "fare_rules.txt",
+                "fare_id,route_id,origin_id,destination_id,contains_id\nF,R,Z1,,\n",
+            ),
+        ]);
+        let findings = validate(&feed);
+        assert_eq!(
+            vec![
+                "warning in zone_id of stops.txt:4 - zone Z2 is not used by any rule in \
+                 fare_rules.txt",
+                "warning in level_id of levels.txt:3 - level L1 is not used by any stop in \
+                 stops.txt",
+            ],
+            messages(&findings)
+        );
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+    }
+}
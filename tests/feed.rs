@@ -1,7 +1,20 @@
+extern crate chrono;
 extern crate csv;
 extern crate transitfeed;
 
-use transitfeed::{FeedReader, Trim};
+use chrono::{Duration, NaiveDate, Utc};
+use std::fs;
+use std::fs::File;
+use transitfeed::feed::MemoryFeedProvider;
+use transitfeed::realtime::{
+    FeedEntity, FeedHeader, FeedMessage, StopScheduleRelationship, StopTimeEvent, StopTimeUpdate,
+    TripDescriptor, TripScheduleRelationship, TripUpdate,
+};
+use transitfeed::{
+    walking_transfers, BookingType, CarsAllowed, ConnectionScan, DurationLimitType, FareMediaType,
+    FareTransferType, FeedReader, LiveSchedule, LoadPolicy, RunIterator, SpatialIndex, Stop,
+    StopFilter, StopServiceType, TimeOffset, Timepoint, TransitFeed, Trim, WALKING_SPEED,
+};
 
 #[test]
 fn test_read_feed_with_reader_options() {
@@ -32,3 +45,919 @@ fn test_fail_bad_zipfiles() {
         "Error didn't match"
     );
 }
+
+#[test]
+fn test_zipfiles_match_extracted_feed() {
+    let zipped = TransitFeed::from_reader(&FeedReader::from_zip("./examples/good_feed.zip").unwrap())
+        .unwrap();
+    let extracted = TransitFeed::from_reader(&FeedReader::new("./examples/good_feed")).unwrap();
+    assert_eq!(extracted.stops, zipped.stops);
+    assert_eq!(extracted.stoptimes, zipped.stoptimes);
+}
+
+#[test]
+fn test_read_interleaved_zipfile_entries() {
+    let feed = FeedReader::from_zip("./examples/good_feed.zip").unwrap();
+    let stops = feed.stops().unwrap();
+    let mut stop_times = feed.stop_times().unwrap();
+
+    let mut count = 0;
+    for stop in stops {
+        assert!(stop.is_ok());
+        assert!(stop_times.next().unwrap().is_ok());
+        count += 1;
+    }
+    assert_eq!(10, count);
+}
+
+#[test]
+fn test_missing_zipfile_entry() {
+    let feed = FeedReader::from_zip("./examples/sample-feed.zip").unwrap();
+    let result = feed.transfers();
+    assert!(result.is_err());
+    assert_eq!(
+        "error parsing ./examples/sample-feed.zip/transfers.txt - file not found in archive",
+        format!("{}", result.err().unwrap())
+    );
+}
+
+#[test]
+fn test_read_zip_from_memory() {
+    let bytes = fs::read("./examples/good_feed.zip").unwrap();
+
+    let borrowed = FeedReader::from_zip_bytes(&bytes[..]).unwrap();
+    assert_eq!(10, borrowed.stops().unwrap().count());
+
+    let owned = FeedReader::from_zip_bytes(bytes).unwrap();
+    assert_eq!(10, owned.stops().unwrap().count());
+}
+
+#[test]
+fn test_read_zip_from_reader() {
+    let file = File::open("./examples/good_feed.zip").unwrap();
+    let feed = TransitFeed::from_reader(&FeedReader::from_zip_reader(file).unwrap()).unwrap();
+    assert_eq!(10, feed.stops.len());
+}
+
+fn memory_feed(stop_times: &'static str) -> MemoryFeedProvider {
+    memory_feed_with(vec![("stop_times.txt", stop_times)])
+}
+
+/// Small in-memory feed with `files` replacing the default contents
+fn memory_feed_with(files: Vec<(&'static str, &'static str)>) -> MemoryFeedProvider {
+    let mut provider = vec![
+        (
+            "agency.txt",
+            "agency_name,agency_url,agency_timezone\nDemo,http://example.com,UTC\n",
+        ),
+        (
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon\nA,Alpha,1.0,2.0\nB,Beta,1.5,2.5\nC,Gamma,1.5,4.5\n",
+        ),
+        (
+            "routes.txt",
+            "route_id,route_short_name,route_long_name,route_type\nR,1,One,3\n",
+        ),
+        ("trips.txt", "route_id,service_id,trip_id\nR,S,T\n"),
+        ("stop_times.txt", STOP_TIMES),
+        (
+            "calendar.txt",
+            "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+             S,1,1,1,1,1,0,0,20260101,20261231\n",
+        ),
+    ];
+    for (name, contents) in files {
+        provider.retain(|&(existing, _)| existing != name);
+        provider.push((name, contents));
+    }
+    provider.into_iter().collect()
+}
+
+const STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                          T,08:00:00,08:00:00,A,1\n\
+                          T,08:10:00,08:10:00,B,2\n";
+
+const BAD_STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                              T,08:00:00,08:00:00,A,1\n\
+                              T,08:10:00,08:10:00,B,second\n";
+
+const UNTIMED_STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence,\
+                                   shape_dist_traveled\n\
+                                   T,08:00:00,08:00:00,A,1,\n\
+                                   T,,,B,2,\n\
+                                   T,08:40:00,08:40:00,C,3,\n";
+
+const MEASURED_STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence,\
+                                   shape_dist_traveled\n\
+                                   T,08:00:00,08:01:00,A,1,0\n\
+                                   T,,,B,2,300\n\
+                                   T,,,C,3,600\n\
+                                   T,08:31:00,08:31:00,A,4,1000\n";
+
+#[test]
+fn test_read_feed_from_memory_provider() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(STOP_TIMES))).unwrap();
+    assert_eq!(
+        Some("Beta"),
+        feed.find_stop("B").unwrap().stop_name.as_deref()
+    );
+    assert_eq!(2, feed.stoptimes.len());
+    assert!(feed.transfers.is_none());
+}
+
+#[test]
+fn test_load_report_for_clean_feed() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(STOP_TIMES))).unwrap();
+    let report = feed.load_report();
+    assert!(report.is_clean());
+    assert_eq!(Some(&2), report.counts.get("stop_times.txt"));
+    assert!(report.missing_files.contains(&"transfers.txt".to_string()));
+    assert!(!report.counts.contains_key("transfers.txt"));
+}
+
+#[test]
+fn test_lenient_load_collects_skipped_records() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(BAD_STOP_TIMES))).unwrap();
+    let report = feed.load_report();
+    assert!(!report.is_clean());
+    assert_eq!(1, feed.stoptimes.len());
+    assert_eq!(Some(&1), report.counts.get("stop_times.txt"));
+    assert_eq!(1, report.skipped.len());
+    assert_eq!(
+        "error parsing stop_sequence in stop_times.txt:3 - invalid digit found in string",
+        format!("{}", report.skipped[0])
+    );
+}
+
+#[test]
+fn test_strict_load_fails_on_first_error() {
+    let result = TransitFeed::from_reader_with_policy(
+        &FeedReader::from_provider(memory_feed(BAD_STOP_TIMES)),
+        LoadPolicy::Strict,
+    );
+    assert_eq!(
+        "error parsing stop_sequence in stop_times.txt:3 - invalid digit found in string",
+        format!("{}", result.err().unwrap())
+    );
+}
+
+#[test]
+fn test_interpolate_by_stop_distance() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(UNTIMED_STOP_TIMES)))
+            .unwrap();
+    assert!(feed.load_report().is_clean());
+    assert_eq!(None, feed.stoptimes[1].arrival_time);
+
+    let run = RunIterator::new(feed.stoptimes.iter()).next().unwrap();
+    let stop_times = run.interpolate(&feed).unwrap();
+    // B is roughly a quarter of the way from A to C
+    let b = stop_times[1].arrival_time.unwrap();
+    assert!(
+        b > TimeOffset::from_hms(8, 10, 0) && b < TimeOffset::from_hms(8, 11, 0),
+        "{}",
+        b
+    );
+    assert_eq!(Some(b), stop_times[1].departure_time);
+    assert_eq!(Timepoint::Approximate, stop_times[1].timepoint);
+    assert_eq!(Timepoint::Exact, stop_times[2].timepoint);
+}
+
+#[test]
+fn test_interpolate_by_shape_distance() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(MEASURED_STOP_TIMES)))
+            .unwrap();
+    let run = RunIterator::new(feed.stoptimes.iter()).next().unwrap();
+    let times: Vec<_> = run
+        .interpolate(&feed)
+        .unwrap()
+        .iter()
+        .map(|stop_time| {
+            (
+                stop_time.arrival_time.unwrap(),
+                stop_time.departure_time.unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        vec![
+            (TimeOffset::from_hms(8, 0, 0), TimeOffset::from_hms(8, 1, 0)),
+            (
+                TimeOffset::from_hms(8, 10, 0),
+                TimeOffset::from_hms(8, 10, 0)
+            ),
+            (
+                TimeOffset::from_hms(8, 19, 0),
+                TimeOffset::from_hms(8, 19, 0)
+            ),
+            (
+                TimeOffset::from_hms(8, 31, 0),
+                TimeOffset::from_hms(8, 31, 0)
+            ),
+        ],
+        times
+    );
+}
+
+#[test]
+fn test_flag_stop_segments() {
+    let provider = memory_feed_with(vec![
+        (
+            "routes.txt",
+            "route_id,route_short_name,route_long_name,route_type,route_sort_order,\
+             continuous_pickup,continuous_drop_off,network_id\n\
+             R,1,One,3,2,3,1,N\n",
+        ),
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id,cars_allowed\nR,S,T,2\n",
+        ),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence,continuous_pickup\n\
+             T,08:00:00,08:00:00,A,1,\n\
+             T,08:10:00,08:10:00,B,2,1\n\
+             T,08:20:00,08:20:00,C,3,0\n",
+        ),
+    ]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    assert!(feed.load_report().is_clean());
+    let route = feed.find_route("R").unwrap();
+    assert_eq!(Some(2), route.route_sort_order);
+    assert_eq!(
+        Some(StopServiceType::NoServiceAvailable),
+        route.continuous_drop_off
+    );
+    assert_eq!(Some("N".to_string()), route.network_id);
+    assert_eq!(
+        CarsAllowed::NoCars,
+        feed.find_trip("T").unwrap().cars_allowed
+    );
+
+    let run = RunIterator::new(feed.stoptimes.iter()).next().unwrap();
+    let segments = run.flag_stop_segments(&feed);
+    // A inherits the route's pickup, B opts out and C is the last stop
+    assert_eq!(1, segments.len());
+    assert_eq!("A", segments[0].from.stop_id);
+    assert_eq!("B", segments[0].to.stop_id);
+    assert_eq!(
+        StopServiceType::MustCoordinateWithDriver,
+        segments[0].pickup
+    );
+}
+
+const LIVE_STOP_TIMES: &str = "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+                               T,08:00:00,08:00:00,A,1\n\
+                               T,08:10:00,08:11:00,B,2\n\
+                               T,08:20:00,08:20:00,C,3\n\
+                               T2,09:00:00,09:00:00,A,1\n\
+                               T2,09:10:00,09:10:00,B,2\n\
+                               T3,10:00:00,10:00:00,A,1\n\
+                               T3,10:10:00,10:10:00,B,2\n\
+                               T3,10:20:00,10:20:00,C,3\n";
+
+fn stop_time_update(sequence: u32, delay: Option<i32>) -> StopTimeUpdate {
+    StopTimeUpdate {
+        stop_sequence: Some(sequence),
+        arrival: delay.map(|delay| StopTimeEvent {
+            delay: Some(delay),
+            ..StopTimeEvent::default()
+        }),
+        ..StopTimeUpdate::default()
+    }
+}
+
+fn trip_update(trip_id: &str, relationship: TripScheduleRelationship) -> TripUpdate {
+    let mut trip = TripDescriptor {
+        trip_id: Some(trip_id.to_string()),
+        ..TripDescriptor::default()
+    };
+    trip.set_schedule_relationship(relationship);
+    TripUpdate {
+        trip,
+        ..TripUpdate::default()
+    }
+}
+
+#[test]
+fn test_connection_scan() {
+    let provider = memory_feed_with(vec![
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id\nR,S,T1\nR,S,T2\nR,S,T3\nR,S,T4\nR,S,T5\n",
+        ),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T1,08:00:00,08:00:00,A,1\n\
+             T1,08:10:00,08:10:00,B,2\n\
+             T2,08:15:00,08:15:00,B,1\n\
+             T2,08:30:00,08:30:00,C,2\n\
+             T3,08:05:00,08:05:00,A,1\n\
+             T3,09:00:00,09:00:00,C,2\n\
+             T4,09:00:00,09:00:00,A,1\n\
+             T4,09:10:00,09:10:00,B,2\n\
+             T5,09:12:00,09:12:00,B,1\n\
+             T5,09:20:00,09:20:00,C,2\n",
+        ),
+        (
+            "transfers.txt",
+            "from_stop_id,to_stop_id,transfer_type,min_transfer_time\nB,B,2,300\n",
+        ),
+    ]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let scan = ConnectionScan::new(&feed, NaiveDate::from_ymd_opt(2026, 3, 2).unwrap());
+    let hms = |hours, minutes| TimeOffset::from_hms(hours, minutes, 0);
+
+    assert_eq!(
+        Some(hms(8, 30)),
+        scan.earliest_arrival("A", "C", hms(7, 50))
+    );
+    assert_eq!(Some(hms(9, 0)), scan.earliest_arrival("A", "C", hms(8, 1)));
+    // five minutes aren't enough to change from T4 to T5
+    assert_eq!(None, scan.earliest_arrival("A", "C", hms(8, 6)));
+    assert_eq!(Some(hms(9, 10)), scan.earliest_arrival("A", "B", hms(8, 6)));
+
+    let profile: Vec<_> = scan
+        .profile("A", "C")
+        .iter()
+        .map(|entry| (entry.departure, entry.arrival))
+        .collect();
+    assert_eq!(
+        vec![(hms(8, 0), hms(8, 30)), (hms(8, 5), hms(9, 0))],
+        profile
+    );
+    assert!(scan.profile("C", "A").is_empty());
+}
+
+#[test]
+fn test_connection_scan_frequency_trips() {
+    let provider = memory_feed_with(vec![(
+        "frequencies.txt",
+        "trip_id,start_time,end_time,headway_secs,exact_times\n\
+         T,08:00:00,09:00:00,1200,1\n",
+    )]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let scan = ConnectionScan::new(&feed, NaiveDate::from_ymd_opt(2026, 3, 2).unwrap());
+    let hms = |hours, minutes| TimeOffset::from_hms(hours, minutes, 0);
+
+    // T leaves A every 20 minutes, not only at 08:00
+    assert_eq!(Some(hms(8, 30)), scan.earliest_arrival("A", "B", hms(8, 5)));
+    let profile: Vec<_> = scan
+        .profile("A", "B")
+        .iter()
+        .map(|entry| (entry.departure, entry.arrival))
+        .collect();
+    assert_eq!(
+        vec![
+            (hms(8, 0), hms(8, 10)),
+            (hms(8, 20), hms(8, 30)),
+            (hms(8, 40), hms(8, 50)),
+        ],
+        profile
+    );
+}
+
+#[test]
+fn test_travel_times() {
+    let provider = memory_feed_with(vec![
+        (
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon\n\
+             A,Alpha,1.0,2.0\nB,Beta,1.5,2.5\nC,Gamma,1.5,4.5\nD,Delta,1.5009,4.5\n",
+        ),
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id\nR,S,T1\nR,S,T2\nR,S,T3\n",
+        ),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T1,08:00:00,08:00:00,A,1\n\
+             T1,08:10:00,08:10:00,B,2\n\
+             T2,08:15:00,08:15:00,B,1\n\
+             T2,08:30:00,08:30:00,C,2\n\
+             T3,08:40:00,08:40:00,A,1\n\
+             T3,08:50:00,08:50:00,C,2\n",
+        ),
+    ]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+    let hms = |hours, minutes| TimeOffset::from_hms(hours, minutes, 0);
+    let minutes = Duration::minutes;
+
+    // D is about 100m from C, a 77 second walk
+    let scan = ConnectionScan::with_walking(&feed, date, 200.0);
+    let times = scan.travel_times("A", hms(8, 0), hms(9, 0), minutes(45));
+    assert_eq!(4, times.len());
+    assert_eq!(Some(&minutes(0)), times.get("A"));
+    assert_eq!(Some(&minutes(10)), times.get("B"));
+    // waiting for T3 beats changing to T2
+    assert_eq!(Some(&minutes(10)), times.get("C"));
+    assert_eq!(Some(&(minutes(10) + Duration::seconds(77))), times.get("D"));
+
+    let times = scan.travel_times("A", hms(8, 0), hms(8, 30), minutes(45));
+    assert_eq!(Some(&minutes(30)), times.get("C"));
+    let times = scan.travel_times("A", hms(8, 0), hms(8, 30), minutes(20));
+    assert_eq!(None, times.get("C"));
+
+    let without_walking = ConnectionScan::new(&feed, date);
+    let times = without_walking.travel_times("A", hms(8, 0), hms(9, 0), minutes(45));
+    assert_eq!(None, times.get("D"));
+
+    // a transfer forbidden from C's station also forbids the walk from C
+    let provider = memory_feed_with(vec![
+        (
+            "stops.txt",
+            "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+             A,Alpha,1.0,2.0,,\n\
+             B,Beta,1.5,2.5,,\n\
+             CS,Gamma,1.5,4.5,1,\n\
+             C,Gamma platform,1.5,4.5,0,CS\n\
+             D,Delta,1.5009,4.5,,\n",
+        ),
+        ("trips.txt", "route_id,service_id,trip_id\nR,S,T3\n"),
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T3,08:40:00,08:40:00,A,1\n\
+             T3,08:50:00,08:50:00,C,2\n",
+        ),
+        (
+            "transfers.txt",
+            "from_stop_id,to_stop_id,transfer_type,min_transfer_time\nCS,D,3,\n",
+        ),
+    ]);
+    let forbidden = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let times = ConnectionScan::with_walking(&forbidden, date, 200.0).travel_times(
+        "A",
+        hms(8, 0),
+        hms(9, 0),
+        minutes(45),
+    );
+    assert_eq!(Some(&minutes(10)), times.get("C"));
+    assert_eq!(None, times.get("D"));
+
+    let matrix = scan.travel_time_matrix(&["A", "B"], hms(8, 0), hms(9, 0), minutes(45));
+    assert_eq!(2, matrix.len());
+    assert_eq!(Some(&minutes(10)), matrix[0].get("C"));
+    assert_eq!(Some(&minutes(15)), matrix[1].get("C"));
+    assert_eq!(None, matrix[1].get("A"));
+}
+
+#[test]
+fn test_live_schedule() {
+    let provider = memory_feed_with(vec![
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id\nR,S,T\nR,S,T2\nR,S,T3\n",
+        ),
+        ("stop_times.txt", LIVE_STOP_TIMES),
+    ]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+    let day_start = date.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+
+    // a minute late overall, two minutes late at B and no data from C
+    let mut delayed = trip_update("T", TripScheduleRelationship::Scheduled);
+    delayed.delay = Some(60);
+    let mut no_data = stop_time_update(3, None);
+    no_data.set_schedule_relationship(StopScheduleRelationship::NoData);
+    delayed.stop_time_update = vec![stop_time_update(2, Some(120)), no_data];
+
+    // skips B and departs C at 10:25
+    let mut skipping = trip_update("T3", TripScheduleRelationship::Scheduled);
+    let mut skipped = stop_time_update(2, None);
+    skipped.set_schedule_relationship(StopScheduleRelationship::Skipped);
+    let mut late = stop_time_update(3, None);
+    late.departure = Some(StopTimeEvent {
+        time: Some(day_start + 10 * 3600 + 25 * 60),
+        ..StopTimeEvent::default()
+    });
+    skipping.stop_time_update = vec![skipped, late];
+
+    let mut added = trip_update("EXTRA", TripScheduleRelationship::Added);
+    added.stop_time_update = vec![StopTimeUpdate {
+        stop_id: Some("C".to_string()),
+        arrival: Some(StopTimeEvent {
+            time: Some(day_start + 11 * 3600),
+            ..StopTimeEvent::default()
+        }),
+        ..StopTimeUpdate::default()
+    }];
+
+    let message = FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".to_string(),
+            ..FeedHeader::default()
+        },
+        entity: vec![
+            delayed,
+            trip_update("T2", TripScheduleRelationship::Canceled),
+            skipping,
+            added,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, update)| FeedEntity {
+            id: index.to_string(),
+            trip_update: Some(update),
+            ..FeedEntity::default()
+        })
+        .collect(),
+    };
+
+    let schedule = LiveSchedule::new(&feed, &message, date, &Utc);
+    let trip_ids: Vec<&str> = schedule
+        .runs
+        .iter()
+        .map(|run| run.trip_id.as_str())
+        .collect();
+    assert_eq!(vec!["EXTRA", "T", "T2", "T3"], trip_ids);
+    let times = |trip_id: &str| -> Vec<(Option<TimeOffset>, Option<TimeOffset>)> {
+        schedule
+            .run(trip_id, None)
+            .unwrap()
+            .stop_times
+            .iter()
+            .map(|stop_time| (stop_time.arrival, stop_time.departure))
+            .collect()
+    };
+    let hms = |hours, minutes| Some(TimeOffset::from_hms(hours, minutes, 0));
+
+    assert_eq!(
+        vec![
+            (hms(8, 1), hms(8, 1)),
+            (hms(8, 12), hms(8, 13)),
+            (None, None)
+        ],
+        times("T")
+    );
+    let canceled = schedule.run("T2", None).unwrap();
+    assert_eq!(
+        TripScheduleRelationship::Canceled,
+        canceled.schedule_relationship
+    );
+    assert_eq!(vec![(None, None), (None, None)], times("T2"));
+    assert_eq!(hms(9, 0), canceled.stop_times[0].scheduled_departure);
+
+    // only the departure from C is predicted and it isn't propagated back to A
+    assert_eq!(
+        vec![(None, None), (None, None), (None, hms(10, 25))],
+        times("T3")
+    );
+    assert_eq!(
+        StopScheduleRelationship::Skipped,
+        schedule.run("T3", None).unwrap().stop_times[1].schedule_relationship
+    );
+
+    let extra = schedule.run("EXTRA", None).unwrap();
+    assert_eq!(TripScheduleRelationship::Added, extra.schedule_relationship);
+    assert_eq!("C", extra.stop_times[0].stop_id);
+    assert_eq!(vec![(hms(11, 0), hms(11, 0))], times("EXTRA"));
+}
+
+#[test]
+fn test_live_schedule_frequency_trips() {
+    let provider = memory_feed_with(vec![
+        (
+            "stop_times.txt",
+            "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+             T,06:00:00,06:00:00,A,1\n\
+             T,06:10:00,06:10:00,B,2\n",
+        ),
+        (
+            "frequencies.txt",
+            "trip_id,start_time,end_time,headway_secs,exact_times\n\
+             T,08:00:00,09:00:00,1800,1\n",
+        ),
+    ]);
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(provider)).unwrap();
+    let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+
+    // only the 08:30 departure is late
+    let mut late = trip_update("T", TripScheduleRelationship::Scheduled);
+    late.trip.start_time = Some("08:30:00".to_string());
+    late.delay = Some(120);
+    let message = FeedMessage {
+        header: FeedHeader {
+            gtfs_realtime_version: "2.0".to_string(),
+            ..FeedHeader::default()
+        },
+        entity: vec![FeedEntity {
+            id: "0".to_string(),
+            trip_update: Some(late),
+            ..FeedEntity::default()
+        }],
+    };
+
+    let schedule = LiveSchedule::new(&feed, &message, date, &Utc);
+    let hms = |hours, minutes| Some(TimeOffset::from_hms(hours, minutes, 0));
+    let starts: Vec<Option<TimeOffset>> = schedule.runs.iter().map(|run| run.start_time).collect();
+    assert_eq!(vec![hms(8, 0), hms(8, 30)], starts);
+    assert!(schedule.run("T", None).is_none());
+
+    let first = schedule.run("T", hms(8, 0)).unwrap();
+    assert_eq!(hms(8, 10), first.stop_times[1].scheduled_arrival);
+    assert_eq!(None, first.stop_times[1].arrival);
+    let second = schedule.run("T", hms(8, 30)).unwrap();
+    assert_eq!(hms(8, 40), second.stop_times[1].scheduled_arrival);
+    assert_eq!(hms(8, 42), second.stop_times[1].arrival);
+}
+
+#[test]
+fn test_interpolate_needs_timed_ends() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(
+        "trip_id,arrival_time,departure_time,stop_id,stop_sequence\n\
+         T,08:00:00,08:00:00,A,1\n\
+         T,,,B,2\n",
+    )))
+    .unwrap();
+    let run = RunIterator::new(feed.stoptimes.iter()).next().unwrap();
+    assert_eq!(None, run.interpolate(&feed));
+}
+
+#[test]
+fn test_station_hierarchy() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![(
+        "stops.txt",
+        "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+         STATION,Central,1.0,2.0,1,\n\
+         A,Platform A,1.0,2.0,0,STATION\n\
+         B,Platform B,1.0,2.0,0,STATION\n\
+         C,Elsewhere,1.5,4.5,0,\n\
+         NORTH,North Entrance,1.0,2.0,2,STATION\n\
+         NODE,Mezzanine,1.0,2.0,3,STATION\n\
+         A1,Front of A,1.0,2.0,4,A\n",
+    )])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+
+    let ids = |stops: Vec<&Stop>| {
+        stops
+            .iter()
+            .map(|stop| stop.stop_id.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec!["A", "B", "NORTH", "NODE"],
+        ids(feed.children("STATION"))
+    );
+    assert_eq!(vec!["A", "B"], ids(feed.platforms("STATION")));
+    assert_eq!(vec!["NORTH"], ids(feed.entrances("STATION")));
+    assert_eq!(vec!["A1"], ids(feed.boarding_areas("A")));
+    assert!(feed.children("C").is_empty());
+
+    assert_eq!("STATION", feed.station("A1").unwrap().stop_id);
+    assert_eq!("STATION", feed.station("STATION").unwrap().stop_id);
+    assert!(feed.station("C").is_none());
+}
+
+#[test]
+fn test_stops_without_position() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![(
+        "stops.txt",
+        "stop_id,stop_name,stop_lat,stop_lon,location_type,parent_station\n\
+         STATION,Central,1.0,2.0,1,\n\
+         A,Platform A,1.0,2.0,0,STATION\n\
+         B,Platform B,1.0005,2.0,0,\n\
+         NODE,,,,3,STATION\n\
+         A1,,,,4,A\n",
+    )])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+    assert_eq!(5, feed.stops.len());
+
+    let node = feed.find_stop("NODE").unwrap();
+    assert_eq!(None, node.stop_name);
+    assert_eq!(None, node.position());
+    assert_eq!(Some((1.0, 2.0)), feed.find_stop("A").unwrap().position());
+    assert_eq!("A1", feed.boarding_areas("A")[0].stop_id);
+
+    // stops without a position are left out of distance queries
+    let index = SpatialIndex::new(&feed);
+    let nearest = index.nearest(1.0, 2.0, 10, &StopFilter::default());
+    assert_eq!(3, nearest.len());
+    assert_eq!(2, walking_transfers(&feed, 100.0, WALKING_SPEED).len());
+}
+
+#[test]
+fn test_read_fares_v2() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![
+        (
+            "fare_media.txt",
+            "fare_media_id,fare_media_name,fare_media_type\nCARD,Transit card,2\n",
+        ),
+        (
+            "fare_products.txt",
+            "fare_product_id,fare_product_name,fare_media_id,amount,currency\n\
+             SINGLE,Single ride,CARD,2.75,USD\n\
+             TRANSFER,Transfer,CARD,0.25,USD\n",
+        ),
+        (
+            "fare_leg_rules.txt",
+            "leg_group_id,network_id,from_area_id,to_area_id,fare_product_id,rule_priority\n\
+             LOCAL,BUS,,,SINGLE,\n",
+        ),
+        (
+            "fare_transfer_rules.txt",
+            "from_leg_group_id,to_leg_group_id,transfer_count,duration_limit,\
+             duration_limit_type,fare_transfer_type,fare_product_id\n\
+             LOCAL,LOCAL,-1,5400,1,0,TRANSFER\n",
+        ),
+        ("areas.txt", "area_id,area_name\nDOWNTOWN,Downtown\n"),
+        ("stop_areas.txt", "area_id,stop_id\nDOWNTOWN,A\n"),
+        ("networks.txt", "network_id,network_name\nBUS,Buses\n"),
+        ("route_networks.txt", "network_id,route_id\nBUS,R\n"),
+        (
+            "timeframes.txt",
+            "timeframe_group_id,start_time,end_time,service_id\n\
+             PEAK,07:00:00,09:00:00,S\n\
+             ALL_DAY,,,S\n",
+        ),
+    ])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+
+    let media = &feed.fare_media.as_ref().unwrap()[0];
+    assert_eq!(FareMediaType::TransitCard, media.fare_media_type);
+    let products = feed.fare_products.as_ref().unwrap();
+    assert_eq!(2.75, products[0].amount);
+    assert_eq!(Some("CARD".to_string()), products[0].fare_media_id);
+    let leg_rule = &feed.fare_leg_rules.as_ref().unwrap()[0];
+    assert_eq!(Some("BUS".to_string()), leg_rule.network_id);
+    assert_eq!(None, leg_rule.from_area_id);
+    assert_eq!(None, leg_rule.rule_priority);
+    let transfer_rule = &feed.fare_transfer_rules.as_ref().unwrap()[0];
+    assert_eq!(Some(-1), transfer_rule.transfer_count);
+    assert_eq!(Some(Duration::minutes(90)), transfer_rule.duration_limit);
+    assert_eq!(
+        Some(DurationLimitType::DepartureToDeparture),
+        transfer_rule.duration_limit_type
+    );
+    assert_eq!(
+        FareTransferType::FirstLegPlusTransfer,
+        transfer_rule.fare_transfer_type
+    );
+    assert_eq!("A", feed.stop_areas.as_ref().unwrap()[0].stop_id);
+    assert_eq!("DOWNTOWN", feed.areas.as_ref().unwrap()[0].area_id);
+    assert_eq!("BUS", feed.networks.as_ref().unwrap()[0].network_id);
+    assert_eq!("R", feed.route_networks.as_ref().unwrap()[0].route_id);
+    let timeframes = feed.timeframes.as_ref().unwrap();
+    assert_eq!(
+        Some(TimeOffset::from_hms(7, 0, 0)),
+        timeframes[0].start_time
+    );
+    assert_eq!(None, timeframes[1].end_time);
+}
+
+#[test]
+fn test_translations() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id,trip_headsign\nR,S,T,Downtown\n",
+        ),
+        (
+            "translations.txt",
+            "table_name,field_name,language,translation,record_id,record_sub_id,field_value\n\
+             stops,stop_name,fr,Alpha (fr),A,,\n\
+             stops,stop_name,fr,Bêta,,,Beta\n\
+             trips,trip_headsign,fr,Centre-ville,,,Downtown\n\
+             stop_times,stop_headsign,fr,Terminus,T,2,\n",
+        ),
+    ])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+    assert_eq!(4, feed.translations.as_ref().unwrap().len());
+
+    assert_eq!(
+        Some("Alpha (fr)"),
+        feed.translate("stops", "stop_name", "A", "fr")
+    );
+    assert_eq!(
+        Some("Bêta"),
+        feed.translate("stops", "stop_name", "B", "fr")
+    );
+    assert_eq!(None, feed.translate("stops", "stop_name", "C", "fr"));
+    assert_eq!(None, feed.translate("stops", "stop_name", "A", "en"));
+    assert_eq!(
+        Some("Centre-ville"),
+        feed.translate("trips", "trip_headsign", "T", "fr")
+    );
+    assert_eq!(
+        Some("Terminus"),
+        feed.translate_stop_time(&feed.stoptimes[1], "stop_headsign", "fr")
+    );
+    assert_eq!(
+        None,
+        feed.translate_stop_time(&feed.stoptimes[0], "stop_headsign", "fr")
+    );
+}
+
+#[test]
+fn test_trip_attributions() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![
+        (
+            "agency.txt",
+            "agency_id,agency_name,agency_url,agency_timezone\nAG,Demo,http://example.com,UTC\n",
+        ),
+        (
+            "trips.txt",
+            "route_id,service_id,trip_id\nR,S,T\nR,S,OTHER\n",
+        ),
+        (
+            "attributions.txt",
+            "attribution_id,agency_id,route_id,trip_id,organization_name,is_producer,\
+             is_operator,is_authority\n\
+             FEED,,,,Open Data Co,1,,\n\
+             AGENCY,AG,,,Transit Authority,,,1\n\
+             ROUTE,,R,,Bus Operator,0,1,0\n\
+             TRIP,,,OTHER,Charter Operator,,1,\n\
+             ELSEWHERE,OTHER_AGENCY,,,Someone Else,1,,\n",
+        ),
+    ])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+
+    let attributions = feed.trip_attributions("T");
+    let ids: Vec<_> = attributions
+        .iter()
+        .map(|attribution| attribution.attribution_id.as_deref().unwrap())
+        .collect();
+    assert_eq!(vec!["FEED", "AGENCY", "ROUTE"], ids);
+    assert!(attributions[0].is_producer && !attributions[0].is_operator);
+    assert!(attributions[1].is_authority);
+    assert!(attributions[2].is_operator && !attributions[2].is_producer);
+    assert_eq!(4, feed.trip_attributions("OTHER").len());
+    assert_eq!(1, feed.trip_attributions("MISSING").len());
+}
+
+#[test]
+fn test_read_flex_feed() {
+    let feed = TransitFeed::from_reader(&FeedReader::from_provider(memory_feed_with(vec![
+        (
+            "stop_times.txt",
+            "trip_id,stop_id,location_id,stop_sequence,start_pickup_drop_off_window,\
+             end_pickup_drop_off_window,pickup_booking_rule_id,drop_off_booking_rule_id\n\
+             T,,ZONE,1,08:00:00,18:00:00,CALL,CALL\n\
+             T,,ZONE,2,08:00:00,18:00:00,CALL,CALL\n",
+        ),
+        (
+            "booking_rules.txt",
+            "booking_rule_id,booking_type,prior_notice_duration_min,phone_number\n\
+             CALL,1,60,555-0100\n",
+        ),
+        (
+            "location_groups.txt",
+            "location_group_id,location_group_name\nAB,A or B\n",
+        ),
+        (
+            "location_group_stops.txt",
+            "location_group_id,stop_id\nAB,A\nAB,B\n",
+        ),
+        (
+            "locations.geojson",
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "id": "ZONE",
+                "properties": {}, "geometry": {"type": "Polygon",
+                "coordinates": [[[1.5, 0.5], [3.0, 0.5], [3.0, 2.0], [1.5, 2.0], [1.5, 0.5]]]}}]}"#,
+        ),
+    ])))
+    .unwrap();
+    assert!(feed.load_report().is_clean());
+    assert_eq!(Some(&1), feed.load_report().counts.get("locations.geojson"));
+
+    let stop_time = &feed.stoptimes[0];
+    assert_eq!("", stop_time.stop_id);
+    assert_eq!(Some("ZONE".to_string()), stop_time.location_id);
+    assert_eq!(None, stop_time.arrival_time);
+    assert_eq!(
+        Some(TimeOffset::from_hms(18, 0, 0)),
+        stop_time.end_pickup_drop_off_window
+    );
+    let booking_rule = &feed.booking_rules.as_ref().unwrap()[0];
+    assert_eq!(BookingType::SameDay, booking_rule.booking_type);
+    assert_eq!(Some(60), booking_rule.prior_notice_duration_min);
+    assert_eq!(2, feed.location_group_stops.as_ref().unwrap().len());
+
+    let zone = &feed.locations.as_ref().unwrap()[0];
+    let alpha = feed.find_stop("A").unwrap();
+    assert!(zone.contains(alpha.stop_lat.unwrap(), alpha.stop_lon.unwrap()));
+    let gamma = feed.find_stop("C").unwrap();
+    assert!(!zone.contains(gamma.stop_lat.unwrap(), gamma.stop_lon.unwrap()));
+}
+
+#[test]
+fn test_missing_locations_are_reported() {
+    let feed =
+        TransitFeed::from_reader(&FeedReader::from_provider(memory_feed(STOP_TIMES))).unwrap();
+    assert!(feed.locations.is_none());
+    assert!(feed
+        .load_report()
+        .missing_files
+        .contains(&"locations.geojson".to_string()));
+}
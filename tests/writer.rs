@@ -0,0 +1,114 @@
+extern crate tempfile;
+extern crate transitfeed;
+
+use std::fs;
+use tempfile::Builder;
+use transitfeed::{walking_transfers, FeedReader, FeedWriter, TransitFeed, WALKING_SPEED};
+
+const FEED_FILES: &[&str] = &[
+    "agency.txt",
+    "stops.txt",
+    "routes.txt",
+    "trips.txt",
+    "stop_times.txt",
+    "calendar.txt",
+    "calendar_dates.txt",
+    "fare_attributes.txt",
+    "fare_rules.txt",
+    "shapes.txt",
+    "frequencies.txt",
+    "transfers.txt",
+];
+
+fn load(path: &str) -> TransitFeed {
+    TransitFeed::from_reader(&FeedReader::new(path)).unwrap()
+}
+
+#[test]
+fn test_write_feed_round_trip() {
+    let original = load("./examples/good_feed");
+    let first = Builder::new().prefix("transitfeed").tempdir().unwrap();
+    let second = Builder::new().prefix("transitfeed").tempdir().unwrap();
+    let first_path = first.path().to_str().unwrap();
+    let second_path = second.path().to_str().unwrap();
+
+    let mut writer = FeedWriter::new(first_path);
+    writer.write_feed(&original).unwrap();
+    writer.finish().unwrap();
+
+    let written = load(first_path);
+    assert_eq!(original.agencies.len(), written.agencies.len());
+    assert_eq!(original.stops, written.stops);
+    assert_eq!(original.stoptimes, written.stoptimes);
+    assert_eq!(original.trips.len(), written.trips.len());
+    assert_eq!(original.calendars.len(), written.calendars.len());
+
+    // a second pass must reproduce the first byte for byte
+    let mut writer = FeedWriter::new(second_path);
+    writer.write_feed(&written).unwrap();
+    writer.finish().unwrap();
+    for filename in FEED_FILES {
+        assert_eq!(
+            fs::read_to_string(first.path().join(filename)).unwrap(),
+            fs::read_to_string(second.path().join(filename)).unwrap(),
+            "{} differs",
+            filename
+        );
+    }
+}
+
+#[test]
+fn test_write_spec_formatted_fields() {
+    let feed = load("./examples/good_feed");
+    let dir = Builder::new().prefix("transitfeed").tempdir().unwrap();
+    let mut writer = FeedWriter::new(dir.path().to_str().unwrap());
+    writer.write_calendars(&feed.calendars).unwrap();
+    writer.write_stop_times(&feed.stoptimes).unwrap();
+
+    let calendar = fs::read_to_string(dir.path().join("calendar.txt")).unwrap();
+    assert!(calendar.contains("FULLW,1,1,1,1,1,1,1,20070101,20251231"));
+    let stop_times = fs::read_to_string(dir.path().join("stop_times.txt")).unwrap();
+    assert!(stop_times.starts_with(
+        "trip_id,arrival_time,departure_time,stop_id,stop_sequence,stop_headsign,pickup_type,drop_off_type"
+    ));
+    assert!(stop_times.contains("STBA,06:00:00,06:00:00,STAGECOACH,0,to airport,1,0"));
+}
+
+#[test]
+fn test_write_zip_round_trip() {
+    let original = load("./examples/good_feed");
+    let dir = Builder::new().prefix("transitfeed").tempdir().unwrap();
+    let zipfile = dir.path().join("feed.zip");
+    let zipfile = zipfile.to_str().unwrap();
+
+    let mut writer = FeedWriter::to_zip(zipfile).unwrap();
+    writer.write_feed(&original).unwrap();
+    writer.finish().unwrap();
+
+    let written = TransitFeed::from_reader(&FeedReader::from_zip(zipfile).unwrap()).unwrap();
+    assert_eq!(original.stops, written.stops);
+    assert_eq!(original.stoptimes, written.stoptimes);
+    assert_eq!(
+        original.transfers.unwrap().len(),
+        written.transfers.unwrap().len()
+    );
+}
+
+#[test]
+fn test_write_walking_transfers() {
+    let feed = load("./examples/good_feed");
+    let transfers = walking_transfers(&feed, 1000.0, WALKING_SPEED);
+    assert!(!transfers.is_empty());
+    let dir = Builder::new().prefix("transitfeed").tempdir().unwrap();
+    let mut writer = FeedWriter::new(dir.path().to_str().unwrap());
+    writer.write_transfers(&transfers).unwrap();
+
+    let reader = FeedReader::new(dir.path().to_str().unwrap());
+    let written: Vec<_> = reader.transfers().unwrap().map(Result::unwrap).collect();
+    assert_eq!(transfers.len(), written.len());
+    for (generated, written) in transfers.iter().zip(&written) {
+        assert_eq!(generated.from_stop_id, written.from_stop_id);
+        assert_eq!(generated.to_stop_id, written.to_stop_id);
+        assert_eq!(generated.min_transfer_time, written.min_transfer_time);
+    }
+}